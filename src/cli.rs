@@ -0,0 +1,820 @@
+//! This module contains the command line interface. Everything but `review` runs without the tui
+//! and prints plain text, so the inbox can be fed from scripts and cron jobs.
+
+// stdlib imports
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+// external imports
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use rusqlite::Connection;
+use serde_json::json;
+// internal imports
+use crate::{
+    config::AppConfig,
+    constants::HEATMAP_WEEKS,
+    database, diff,
+    error::{AppError, AppResult, DatabaseResult},
+    feeds, grading, judge,
+    types::{Item, Solution},
+};
+
+#[derive(Parser)]
+#[command(
+    name = "spaced-programming",
+    version,
+    about = "Spaced repetition for competitive programming problems"
+)]
+pub struct Cli {
+    /// database to use instead of the one in the config
+    #[arg(long, global = true, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    /// starts the review tui when left out
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// add problem urls to the bottom of the inbox
+    #[command(group(ArgGroup::new("sources").required(true).multiple(true).args(["urls", "file"])))]
+    Add {
+        urls: Vec<String>,
+        /// read urls from a file, one per line ('-' reads stdin). Blank lines and lines starting
+        /// with '#' are skipped
+        #[arg(long, short, value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+    /// tag review items, tags restrict `due` and `review` to some kinds of problems
+    Tags {
+        #[command(subcommand)]
+        command: TagsCommand,
+    },
+    /// print the reviews of an item as tab separated id, timestamp, grade, solve time in seconds,
+    /// interval before and after, and verdict
+    History {
+        item_id: u64,
+        /// print the saved versions of the item's notes instead, oldest first
+        #[arg(long)]
+        notes: bool,
+    },
+    /// inspect the inbox
+    Inbox {
+        #[command(subcommand)]
+        command: InboxCommand,
+    },
+    /// manage the rss and atom feeds new problems are pulled from
+    Feed {
+        #[command(subcommand)]
+        command: FeedCommand,
+    },
+    /// add the new entries of every feed to the inbox
+    SyncFeeds,
+    /// manage the sample tests of an item
+    Samples {
+        #[command(subcommand)]
+        command: SamplesCommand,
+    },
+    /// archive solutions and see how they changed
+    Solutions {
+        #[command(subcommand)]
+        command: SolutionsCommand,
+    },
+    /// set how long solving an item should take, grades are suggested based on it
+    Target {
+        item_id: u64,
+        #[arg(required_unless_present = "clear")]
+        minutes: Option<u64>,
+        /// go by the target for the item's difficulty again
+        #[arg(long, conflicts_with = "minutes")]
+        clear: bool,
+    },
+    /// run a solution against the sample tests of an item
+    Judge {
+        item_id: u64,
+        /// defaults to the newest solution in the configured solution directory
+        solution: Option<PathBuf>,
+    },
+    /// print the urls of the items that are due, one per line
+    Due {
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// review due items in the tui
+    Review {
+        #[command(flatten)]
+        filter: TagFilter,
+    },
+    /// print review statistics
+    Stats {
+        /// print the statistics as a json object
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Restricts the due items to the ones with some tags, e.g. for a session of only graph problems
+#[derive(Args, Default, Clone)]
+pub struct TagFilter {
+    /// only items with this tag, repeat it to allow several tags
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// only items with all of the tags instead of any of them
+    #[arg(long, requires = "tags")]
+    pub all: bool,
+}
+
+impl TagFilter {
+    /// the items due at {timestamp} that pass the filter, all of them without tags
+    pub fn due_items(&self, conn: &Connection, timestamp: u64) -> DatabaseResult<Vec<Item>> {
+        let tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+        database::get_due_items_with_tags(conn, timestamp, &tags, self.all)
+    }
+}
+
+#[derive(Subcommand)]
+pub enum InboxCommand {
+    /// print the inbox in queue order as tab separated id, url and tags
+    List,
+    /// print the entries the daily drip promoted as tab separated time of the drip, item id and
+    /// url
+    Dripped,
+    /// change the place of an entry in the queue
+    Move {
+        id: u64,
+        /// move the entry to the top, it is promoted next
+        #[arg(long, conflicts_with_all = ["bottom", "to"])]
+        top: bool,
+        /// move the entry to the bottom
+        #[arg(long, conflicts_with = "to")]
+        bottom: bool,
+        /// move the entry to this place in the queue, 1 is the top
+        #[arg(long, value_name = "POSITION", required_unless_present_any = ["top", "bottom"])]
+        to: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagsCommand {
+    /// print every tag in use, one per line
+    List,
+    /// add tags to an item
+    Add {
+        item_id: u64,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// remove a tag from an item
+    Remove { item_id: u64, tag: String },
+}
+
+#[derive(Subcommand)]
+pub enum SamplesCommand {
+    /// replace the samples of an item with the `name.in`/`name.ans` (or `name.out`) pairs in a
+    /// directory
+    Import { item_id: u64, dir: PathBuf },
+    /// print the samples of an item
+    List { item_id: u64 },
+}
+
+#[derive(Subcommand)]
+pub enum SolutionsCommand {
+    /// archive a solution file for an item, its extension is its language
+    Add { item_id: u64, file: PathBuf },
+    /// print the solutions of an item as tab separated id, language, timestamp and review id
+    List { item_id: u64 },
+    /// print how the latest solution of an item differs from the one before it
+    Diff { item_id: u64 },
+}
+
+#[derive(Subcommand)]
+pub enum FeedCommand {
+    /// add a feed by its http(s) url or the path of a local file
+    Add { url: String },
+    /// print the feeds as tab separated id, url and the time of the last sync
+    List,
+    /// stop syncing a feed, the problems it added stay in the inbox
+    Remove { id: u64 },
+}
+
+/// runs every command except `review`, writing its output to {out}
+pub fn run(
+    command: Command,
+    conn: &mut Connection,
+    config: &AppConfig,
+    out: &mut impl Write,
+) -> AppResult<()> {
+    match command {
+        Command::Add { mut urls, file } => {
+            if let Some(file) = file {
+                urls.extend(read_urls(&file).map_err(AppError::InputError)?);
+            }
+            let report = database::add_urls_to_inbox(conn, urls)?;
+            writeln!(out, "added {} urls to the inbox", report.added.len())?;
+            for url in report.already_in_inbox {
+                writeln!(out, "already in the inbox: {url}")?;
+            }
+            for url in report.already_items {
+                writeln!(out, "already an item: {url}")?;
+            }
+        }
+        Command::Inbox {
+            command:
+                InboxCommand::Move {
+                    id,
+                    top,
+                    bottom,
+                    to,
+                },
+        } => match (top, bottom, to) {
+            (true, _, _) => database::move_inbox_item_to_top(conn, id)?,
+            (_, true, _) => database::move_inbox_item_to_bottom(conn, id)?,
+            (_, _, Some(position)) => {
+                database::move_inbox_item(conn, id, position.saturating_sub(1))?
+            }
+            // clap requires one of them
+            _ => unreachable!("inbox move without a destination"),
+        },
+        Command::Inbox {
+            command: InboxCommand::Dripped,
+        } => {
+            for promotion in database::get_drip_promotions(conn)? {
+                let item_id = promotion.item_id.map_or("-".into(), |id| id.to_string());
+                writeln!(out, "{}\t{item_id}\t{}", promotion.timestamp, promotion.url)?;
+            }
+        }
+        Command::Inbox {
+            command: InboxCommand::List,
+        } => {
+            let n_inbox = database::count_inbox_items(conn)? as usize;
+            for url_item in database::get_n_urls_from_inbox(conn, n_inbox)? {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}",
+                    url_item.id,
+                    url_item.url,
+                    url_item.tags.join(",")
+                )?;
+            }
+        }
+        Command::Tags { command } => match command {
+            TagsCommand::List => {
+                for tag in database::list_tags(conn)? {
+                    writeln!(out, "{tag}")?;
+                }
+            }
+            TagsCommand::Add { item_id, tags } => {
+                let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+                database::add_item_tags(conn, item_id, &tags)?;
+            }
+            TagsCommand::Remove { item_id, tag } => database::remove_item_tag(conn, item_id, &tag)?,
+        },
+        Command::History {
+            item_id,
+            notes: true,
+        } => {
+            for revision in database::get_note_history(conn, item_id)? {
+                writeln!(out, "=== {}", revision.timestamp)?;
+                writeln!(out, "{}", revision.notes)?;
+            }
+        }
+        Command::History { item_id, .. } => {
+            for review in database::get_item_reviews(conn, item_id)? {
+                let duration = review
+                    .duration
+                    .map_or("-".into(), |d| d.as_secs().to_string());
+                let verdict = review.verdict.map_or("-", |verdict| verdict.code());
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{duration}\t{}\t{}\t{verdict}",
+                    review.id,
+                    review.timestamp,
+                    grading::grade_name(&review.grade),
+                    review.interval_before,
+                    review.interval_after
+                )?;
+            }
+        }
+        Command::Feed { command } => match command {
+            FeedCommand::Add { url } => {
+                let id = database::add_feed(conn, &url)?;
+                writeln!(out, "added feed {id}")?;
+            }
+            FeedCommand::List => {
+                for feed in database::get_feeds(conn)? {
+                    let last_synced = feed.last_synced.map_or("-".into(), |t| t.to_string());
+                    writeln!(out, "{}\t{}\t{last_synced}", feed.id, feed.url)?;
+                }
+            }
+            FeedCommand::Remove { id } => database::remove_feed(conn, id)?,
+        },
+        Command::SyncFeeds => {
+            let now = database::current_timestamp();
+            for (feed, result) in feeds::sync_feeds(conn, now)? {
+                match result {
+                    Ok(added) => writeln!(out, "{}: {added} new problems", feed.url)?,
+                    Err(err) => writeln!(out, "{}: {err}", feed.url)?,
+                }
+            }
+        }
+        Command::Samples { command } => match command {
+            SamplesCommand::Import { item_id, dir } => {
+                let samples = judge::import_samples(&dir).map_err(AppError::InputError)?;
+                database::replace_sample_tests(conn, item_id, &samples)?;
+                writeln!(out, "imported {} samples", samples.len())?;
+            }
+            SamplesCommand::List { item_id } => {
+                let samples = database::get_sample_tests(conn, item_id)?;
+                write!(out, "{}", judge::samples_to_text(&samples))?;
+            }
+        },
+        Command::Solutions { command } => match command {
+            SolutionsCommand::Add { item_id, file } => {
+                let source = std::fs::read_to_string(&file).map_err(AppError::InputError)?;
+                let language = file.extension().unwrap_or_default().to_string_lossy();
+                let solution = Solution {
+                    id: 0,
+                    item_id,
+                    review_id: None,
+                    language: language.into_owned(),
+                    source,
+                    timestamp: database::current_timestamp(),
+                };
+                let id = database::add_solution(conn, &solution)?;
+                writeln!(out, "added solution {id}")?;
+            }
+            SolutionsCommand::List { item_id } => {
+                for solution in database::get_item_solutions(conn, item_id)? {
+                    let review_id = solution.review_id.map_or("-".into(), |id| id.to_string());
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}\t{review_id}",
+                        solution.id, solution.language, solution.timestamp
+                    )?;
+                }
+            }
+            SolutionsCommand::Diff { item_id } => {
+                let solutions = database::get_item_solutions(conn, item_id)?;
+                // the first solution is all new
+                let (previous, latest) = match &solutions[..] {
+                    [] => ("", ""),
+                    [latest] => ("", latest.source.as_str()),
+                    [.., previous, latest] => (previous.source.as_str(), latest.source.as_str()),
+                };
+                write!(
+                    out,
+                    "{}",
+                    diff::format_diff(&diff::diff_lines(previous, latest))
+                )?;
+            }
+        },
+        Command::Target {
+            item_id, minutes, ..
+        } => {
+            let target = minutes.map(|minutes| Duration::from_secs(minutes * 60));
+            database::set_item_target(conn, item_id, target)?;
+        }
+        Command::Judge { item_id, solution } => {
+            let judge_config = &config.judge;
+            let solution = match solution {
+                Some(solution) => solution,
+                None => judge::find_solution(&judge_config.solution_dir, &judge_config.languages)
+                    .map_err(AppError::JudgeError)?
+                    .ok_or_else(|| {
+                        AppError::JudgeError(std::io::Error::new(
+                            std::io::ErrorKind::NotFound,
+                            format!("no solution in {}", judge_config.solution_dir.display()),
+                        ))
+                    })?,
+            };
+            let samples = database::get_sample_tests(conn, item_id)?;
+            let report = judge::judge(&solution, &samples, judge_config)?;
+            for (i, verdict) in report.results.iter().enumerate() {
+                writeln!(out, "sample {}: {}", i + 1, verdict.code())?;
+            }
+            writeln!(out, "{}", report.verdict.code())?;
+            if !report.details.is_empty() {
+                writeln!(out, "{}", report.details)?;
+            }
+        }
+        Command::Due { filter } => {
+            let now = database::current_timestamp();
+            for item in filter.due_items(conn, now)? {
+                writeln!(out, "{}", item.url)?;
+            }
+        }
+        Command::Review { .. } => unreachable!("the review tui is started from main"),
+        Command::Stats { json } => {
+            let now = database::current_timestamp();
+            let stats = database::get_stats(conn, now)?;
+            let n_reviews: u32 = stats.reviews_per_day.iter().sum();
+            let average_solve_time = stats.average_solve_time.map(|d| d.as_secs());
+            if json {
+                let per_tag: serde_json::Map<String, serde_json::Value> = stats
+                    .retention_per_tag
+                    .iter()
+                    .map(|(tag, rate)| (tag.clone(), json!(rate)))
+                    .collect();
+                let stats_json = json!({
+                    "due": database::count_due_items(conn, now)?,
+                    "new": database::count_inbox_items(conn)?,
+                    "total": database::count_items(conn)?,
+                    "heatmap_start_day": stats.heatmap_start_day,
+                    "reviews_per_day": stats.reviews_per_day,
+                    "retention": stats.retention,
+                    "retention_per_tag": per_tag,
+                    "due_forecast": stats.due_forecast,
+                    "average_solve_time_secs": average_solve_time,
+                });
+                writeln!(out, "{stats_json}")?;
+            } else {
+                let percent = |rate: f64| format!("{:.0}%", rate * 100.0);
+                writeln!(out, "due: {}", database::count_due_items(conn, now)?)?;
+                writeln!(out, "new: {}", database::count_inbox_items(conn)?)?;
+                writeln!(out, "total: {}", database::count_items(conn)?)?;
+                writeln!(out, "reviews, last {HEATMAP_WEEKS} weeks: {n_reviews}")?;
+                writeln!(
+                    out,
+                    "retention: {}",
+                    stats.retention.map_or("-".into(), percent)
+                )?;
+                for (tag, rate) in &stats.retention_per_tag {
+                    writeln!(out, "  {tag}: {}", percent(*rate))?;
+                }
+                let average = average_solve_time.map_or("-".into(), |secs| format!("{secs}s"));
+                writeln!(out, "average solve time: {average}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// the urls in {path} ('-' for stdin), skipping blank lines and comments
+fn read_urls(path: &Path) -> std::io::Result<Vec<String>> {
+    let lines: Vec<String> = if path == Path::new("-") {
+        std::io::stdin().lock().lines().collect::<Result<_, _>>()?
+    } else {
+        std::fs::read_to_string(path)?
+            .lines()
+            .map(String::from)
+            .collect()
+    };
+    Ok(lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::create_temp_dir;
+    use crate::types::{ReviewLog, UserReview, Verdict};
+    use serial_test::serial;
+
+    /// util function that runs a command line against {conn} and returns what it printed
+    fn run_args(conn: &mut Connection, args: &[&str]) -> String {
+        let cli = Cli::try_parse_from(["spaced-programming"].iter().chain(args)).unwrap();
+        let mut out = vec![];
+        run(cli.command.unwrap(), conn, &AppConfig::default(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn global_db_flag() {
+        let cli = Cli::try_parse_from(["spaced-programming", "due", "--db", "/tmp/other.db"]);
+        assert!(cli.unwrap().db == Some(PathBuf::from("/tmp/other.db")));
+        // no subcommand means the tui
+        let cli = Cli::try_parse_from(["spaced-programming"]).unwrap();
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn add_urls_and_list_inbox() {
+        let (db_path, cleanup) = create_temp_dir("cli_add_urls_and_list_inbox");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        let file = db_path.with_file_name("urls.txt");
+        std::fs::write(
+            &file,
+            "# from the weekly contest\nhttps://open.kattis.com/problems/faktor\n\n  https://open.kattis.com/problems/autori  \n",
+        )
+        .unwrap();
+
+        let out = run_args(
+            &mut conn,
+            &[
+                "add",
+                "https://open.kattis.com/problems/hello",
+                "--file",
+                file.to_str().unwrap(),
+                "http://kattis.com/problems/hello/",
+            ],
+        );
+        assert!(
+            out == "added 3 urls to the inbox\n\
+                    already in the inbox: https://open.kattis.com/problems/hello\n"
+        );
+        let out = run_args(&mut conn, &["inbox", "list"]);
+        assert!(
+            out == "1\thttps://open.kattis.com/problems/hello\t\n\
+                    2\thttps://open.kattis.com/problems/faktor\t\n\
+                    3\thttps://open.kattis.com/problems/autori\t\n"
+        );
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    fn add_needs_urls_or_a_file() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["spaced-programming"].iter().chain(args));
+        assert!(parse(&["add"]).is_err());
+        assert!(parse(&["add", "https://open.kattis.com/problems/hello"]).is_ok());
+        assert!(parse(&["add", "--file", "urls.txt"]).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn move_inbox_entries() {
+        let (db_path, cleanup) = create_temp_dir("cli_move_inbox_entries");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &[
+                "add",
+                "https://open.kattis.com/problems/hello",
+                "https://open.kattis.com/problems/faktor",
+                "https://open.kattis.com/problems/autori",
+            ],
+        );
+        let inbox_ids = |conn: &mut Connection| -> Vec<String> {
+            let out = run_args(conn, &["inbox", "list"]);
+            out.lines()
+                .map(|line| line.split('\t').next().unwrap().to_string())
+                .collect()
+        };
+
+        run_args(&mut conn, &["inbox", "move", "3", "--top"]);
+        assert!(inbox_ids(&mut conn) == vec!["3", "1", "2"]);
+        run_args(&mut conn, &["inbox", "move", "3", "--bottom"]);
+        assert!(inbox_ids(&mut conn) == vec!["1", "2", "3"]);
+        run_args(&mut conn, &["inbox", "move", "1", "--to", "2"]);
+        assert!(inbox_ids(&mut conn) == vec!["2", "1", "3"]);
+        // a destination is required, and only one
+        let parse = |args: &[&str]| Cli::try_parse_from(["spaced-programming"].iter().chain(args));
+        assert!(parse(&["inbox", "move", "1"]).is_err());
+        assert!(parse(&["inbox", "move", "1", "--top", "--to", "2"]).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn due_prints_urls() {
+        let (db_path, cleanup) = create_temp_dir("cli_due_prints_urls");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        assert!(run_args(&mut conn, &["due"]).is_empty());
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+        let out = run_args(&mut conn, &["due"]);
+        assert!(out == "https://open.kattis.com/problems/hello\n");
+        database::add_item_tags(&conn, 1, &["dp"]).unwrap();
+        assert!(run_args(&mut conn, &["due", "--tag", "dp"]) == out);
+        assert!(run_args(&mut conn, &["due", "--tag", "graphs"]).is_empty());
+        assert!(run_args(&mut conn, &["due", "--tag", "graphs", "--tag", "dp"]) == out);
+        let all = run_args(
+            &mut conn,
+            &["due", "--tag", "graphs", "--tag", "dp", "--all"],
+        );
+        assert!(all.is_empty());
+        // matching all tags needs some tags
+        assert!(Cli::try_parse_from(["spaced-programming", "review", "--all"]).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn add_list_and_sync_feeds() {
+        let (db_path, cleanup) = create_temp_dir("cli_add_list_and_sync_feeds");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        let feed = db_path.with_file_name("feed.xml");
+        std::fs::write(
+            &feed,
+            "<rss><channel><item><link>https://open.kattis.com/problems/hello</link></item></channel></rss>",
+        )
+        .unwrap();
+        let feed = feed.to_str().unwrap();
+
+        assert!(run_args(&mut conn, &["feed", "add", feed]) == "added feed 1\n");
+        assert!(run_args(&mut conn, &["feed", "list"]) == format!("1\t{feed}\t-\n"));
+        let out = run_args(&mut conn, &["sync-feeds"]);
+        assert!(out == format!("{feed}: 1 new problems\n"));
+        let out = run_args(&mut conn, &["inbox", "list"]);
+        assert!(out == "1\thttps://open.kattis.com/problems/hello\t\n");
+        run_args(&mut conn, &["feed", "remove", "1"]);
+        assert!(run_args(&mut conn, &["feed", "list"]).is_empty());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn import_samples_and_judge() {
+        let (db_path, cleanup) = create_temp_dir("cli_import_samples_and_judge");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+        let dir = db_path.with_file_name("samples");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("1.in"), "").unwrap();
+        std::fs::write(dir.join("1.ans"), "Hello World!\n").unwrap();
+        let dir = dir.to_str().unwrap();
+
+        let out = run_args(&mut conn, &["samples", "import", "1", dir]);
+        assert!(out == "imported 1 samples\n");
+        let out = run_args(&mut conn, &["samples", "list", "1"]);
+        assert!(out.ends_with("=== input\n=== output\nHello World!\n"));
+        // python is configured out of the box
+        let solution = db_path.with_file_name("hello.py");
+        std::fs::write(&solution, "print('Hello World!')\n").unwrap();
+        if std::process::Command::new("python3")
+            .arg("--version")
+            .output()
+            .is_ok()
+        {
+            let out = run_args(&mut conn, &["judge", "1", solution.to_str().unwrap()]);
+            assert!(out == "sample 1: AC\nAC\n");
+        }
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn judge_without_samples() {
+        let (db_path, cleanup) = create_temp_dir("cli_judge_without_samples");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+        let solution = db_path.with_file_name("hello.py");
+        std::fs::write(&solution, "print('Hello World!')\n").unwrap();
+
+        // neither an item without samples nor one that does not exist passes
+        for item_id in ["1", "2"] {
+            let args = [
+                "spaced-programming",
+                "judge",
+                item_id,
+                solution.to_str().unwrap(),
+            ];
+            let cli = Cli::try_parse_from(args).unwrap();
+            let mut out = vec![];
+            let res = run(
+                cli.command.unwrap(),
+                &mut conn,
+                &AppConfig::default(),
+                &mut out,
+            );
+            assert!(
+                matches!(res, Err(AppError::JudgeError(err)) if err.to_string() == "no sample tests")
+            );
+            assert!(out.is_empty());
+        }
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn archive_and_diff_solutions() {
+        let (db_path, cleanup) = create_temp_dir("cli_archive_and_diff_solutions");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+        let file = db_path.with_file_name("hello.py");
+        let file_arg = file.to_str().unwrap();
+
+        assert!(run_args(&mut conn, &["solutions", "diff", "1"]).is_empty());
+        std::fs::write(&file, "print('Hello World')\n").unwrap();
+        assert!(run_args(&mut conn, &["solutions", "add", "1", file_arg]) == "added solution 1\n");
+        let out = run_args(&mut conn, &["solutions", "diff", "1"]);
+        assert!(out == "+ print('Hello World')\n");
+        std::fs::write(&file, "# greet\nprint('Hello World!')\n").unwrap();
+        run_args(&mut conn, &["solutions", "add", "1", file_arg]);
+        let out = run_args(&mut conn, &["solutions", "diff", "1"]);
+        assert!(out == "- print('Hello World')\n+ # greet\n+ print('Hello World!')\n");
+        let out = run_args(&mut conn, &["solutions", "list", "1"]);
+        assert!(out.lines().count() == 2);
+        assert!(out.starts_with("1\tpy\t"));
+        assert!(out.ends_with("\t-\n"));
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn tag_items() {
+        let (db_path, cleanup) = create_temp_dir("cli_tag_items");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+
+        run_args(&mut conn, &["tags", "add", "1", "Graphs", "dp"]);
+        assert!(run_args(&mut conn, &["tags", "list"]) == "dp\ngraphs\n");
+        let due = run_args(&mut conn, &["due", "--tag", "graphs"]);
+        assert!(due == "https://open.kattis.com/problems/hello\n");
+        run_args(&mut conn, &["tags", "remove", "1", "graphs"]);
+        assert!(run_args(&mut conn, &["tags", "list"]) == "dp\n");
+        assert!(run_args(&mut conn, &["due", "--tag", "graphs"]).is_empty());
+        let parse = |args: &[&str]| Cli::try_parse_from(["spaced-programming"].iter().chain(args));
+        assert!(parse(&["tags", "add", "1"]).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn history_of_reviews_and_notes() {
+        let (db_path, cleanup) = create_temp_dir("cli_history_of_reviews_and_notes");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+        assert!(run_args(&mut conn, &["history", "1"]).is_empty());
+
+        let item = database::get_item(&conn, 1).unwrap();
+        let log = ReviewLog {
+            id: 0,
+            item_id: 1,
+            timestamp: 100,
+            grade: UserReview::TooEasy,
+            duration: Some(Duration::from_secs(90)),
+            interval_before: 0,
+            interval_after: 4,
+            difficulty_before: 0.0,
+            difficulty_after: 0.0,
+            verdict: Some(Verdict::Accepted),
+        };
+        database::record_review(&mut conn, item, &log).unwrap();
+        assert!(run_args(&mut conn, &["history", "1"]) == "1\t100\teasy\t90\t0\t4\tAC\n");
+
+        database::update_item_notes(&mut conn, 1, "print it", 200).unwrap();
+        database::update_item_notes(&mut conn, 1, "just print it", 300).unwrap();
+        let notes = run_args(&mut conn, &["history", "1", "--notes"]);
+        assert!(notes == "=== 200\nprint it\n=== 300\njust print it\n");
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn set_and_clear_targets() {
+        let (db_path, cleanup) = create_temp_dir("cli_set_and_clear_targets");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        database::promote_inbox_item(&mut conn, 1, &AppConfig::default().scheduling).unwrap();
+
+        run_args(&mut conn, &["target", "1", "45"]);
+        let target = database::get_item(&conn, 1).unwrap().target;
+        assert!(target == Some(Duration::from_secs(45 * 60)));
+        run_args(&mut conn, &["target", "1", "--clear"]);
+        assert!(database::get_item(&conn, 1).unwrap().target.is_none());
+        let parse = |args: &[&str]| Cli::try_parse_from(["spaced-programming"].iter().chain(args));
+        assert!(parse(&["target", "1"]).is_err());
+        assert!(parse(&["target", "1", "45", "--clear"]).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn stats_as_json() {
+        let (db_path, cleanup) = create_temp_dir("cli_stats_as_json");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        run_args(
+            &mut conn,
+            &["add", "https://open.kattis.com/problems/hello"],
+        );
+        let out = run_args(&mut conn, &["stats", "--json"]);
+        let stats: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert!(stats["new"] == 1);
+        assert!(stats["total"] == 0);
+        assert!(stats["retention"].is_null());
+        assert!(stats["due_forecast"].as_array().unwrap().len() == crate::constants::FORECAST_DAYS);
+        assert!(cleanup().is_ok());
+    }
+}
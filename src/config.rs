@@ -0,0 +1,577 @@
+//! This module contains the application config. It is read from
+//! `$XDG_CONFIG_HOME/spaced-programming/config.toml`, every setting missing from the file keeps
+//! its default value.
+
+// stdlib imports
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+// external imports
+use serde::Deserialize;
+// internal imports
+use crate::{
+    error::{AppError, AppResult},
+    planner::QueueOrder,
+    scheduling::Algorithm,
+};
+
+const APP_DIR: &str = "spaced-programming";
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    // the database with our items, defaults to $XDG_DATA_HOME/spaced-programming/items.db
+    pub db_path: PathBuf,
+    // how many first reviews of new items (promoted but never reviewed) a day's queue holds at
+    // most. It doesn't promote anything, `drip.items_per_day` does: new items the queue has no
+    // room for stay due for the next days, and 0 keeps them out of the queue but not the drip
+    pub new_items_per_day: u32,
+    // how many reviews we want to do per day at most, first reviews of new items not included
+    pub reviews_per_day: u32,
+    // how long we want to spend on reviews per day at most, no limit if left out
+    pub minutes_per_day: Option<u32>,
+    // command used to open problem urls, the url is passed as the last argument
+    pub opener_command: String,
+    // saved problem pages, {page_cache_dir}/{site}/{problem_id}.html, used for titles and
+    // difficulties. Defaults to $XDG_CACHE_HOME/spaced-programming/pages
+    pub page_cache_dir: PathBuf,
+    pub timer: TimerConfig,
+    pub judge: JudgeConfig,
+    pub scheduling: SchedulingConfig,
+    pub grading: GradingConfig,
+    pub backlog: BacklogConfig,
+    pub queue: QueueConfig,
+    pub drip: DripConfig,
+    pub key_bindings: KeyBindings,
+}
+
+/// What to do when more reviews are due than the daily limit allows, e.g. after a week away
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BacklogConfig {
+    // review the items we are most likely to have forgotten first
+    pub recovery: bool,
+    // move the reviews that do not fit into today to the coming days, at most this many days
+    // ahead. 0 leaves them due
+    pub spread_days: u32,
+}
+
+/// The order of the due queue, it can be switched on the main screen too
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    pub order: QueueOrder,
+    // the random order is shuffled with this seed and the day
+    pub seed: u64,
+}
+
+/// Inbox entries promoted to review items automatically, the first time the app starts on a day
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DripConfig {
+    // 0 leaves promoting to us. Promoted items wait for a slot of `new_items_per_day`
+    pub items_per_day: u32,
+    pub balance: DripBalance,
+}
+
+/// Which inbox entries the drip promotes
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DripBalance {
+    // the ones at the top of the queue
+    Queue,
+    // the top entries of each tag in turn, by the first tag of an entry
+    Tags,
+    // the top entries of each site in turn
+    Sites,
+}
+
+/// Target times, in minutes, the suggested grade is based on. Items can have a target of their
+/// own, see `spaced-programming target`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GradingConfig {
+    // for problems without a target of their own or for their difficulty
+    pub target_minutes: u32,
+    // by difficulty as the problem source reports it, e.g. "Medium". A numeric key like "1600"
+    // also covers every numeric difficulty up to it that has no target of its own
+    pub difficulty_targets: BTreeMap<String, u32>,
+}
+
+impl Default for GradingConfig {
+    fn default() -> Self {
+        Self {
+            target_minutes: 30,
+            difficulty_targets: BTreeMap::from([
+                ("Easy".into(), 15),
+                ("Medium".into(), 30),
+                ("Hard".into(), 60),
+            ]),
+        }
+    }
+}
+
+/// Which algorithm schedules an item. Tags act as decks: an item with a tag listed under `tags`
+/// is scheduled by that tag's algorithm (the first one in alphabetical order if it has several),
+/// every other item by `algorithm`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulingConfig {
+    pub algorithm: Algorithm,
+    pub tags: BTreeMap<String, Algorithm>,
+    // how far a new due date may move to a less busy day, as a fraction of the interval. 0 turns
+    // load balancing off
+    pub fuzz: f64,
+    pub max_fuzz_days: u32,
+}
+
+impl SchedulingConfig {
+    pub fn algorithm_for(&self, tags: &[String]) -> Algorithm {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tags.contains(tag))
+            .map_or(self.algorithm, |(_, algorithm)| *algorithm)
+    }
+}
+
+impl Default for SchedulingConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::SpacedRs,
+            tags: BTreeMap::new(),
+            fuzz: 0.05,
+            max_fuzz_days: 7,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimerConfig {
+    // start the timer as soon as the problem is opened instead of waiting for a key press
+    pub auto_start: bool,
+    // show the running time while solving, some of us solve better without a clock
+    pub show_elapsed: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct JudgeConfig {
+    // the newest file in this directory with the extension of a known language is judged
+    pub solution_dir: PathBuf,
+    pub time_limit_ms: u64,
+    pub comparison: Comparison,
+    // the largest difference between two numbers that are still considered equal
+    pub float_tolerance: f64,
+    // how to build and run solutions, by file extension. Languages in the config file are added
+    // to (or replace) the default ones
+    pub languages: BTreeMap<String, LanguageConfig>,
+}
+
+/// Commands are split on whitespace. {src} is replaced with the solution file and {bin} with a
+/// path in a temporary directory the compiler can write to.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageConfig {
+    #[serde(default)]
+    pub compile: Option<String>,
+    pub run: String,
+}
+
+/// How the output of a solution is compared to the expected output
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    // byte for byte
+    Exact,
+    // the same tokens, however they are spaced
+    Whitespace,
+    // like whitespace, but numbers may differ by float_tolerance
+    Float,
+}
+
+/// Keys for the actions that have a letter. Space and escape always keep their meaning.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeyBindings {
+    pub review: char,
+    pub promote_new: char,
+    pub stats: char,
+    pub sync_feeds: char,
+    pub inbox: char,
+    pub edit_notes: char,
+    pub edit_samples: char,
+    pub run_judge: char,
+    pub attach_solution: char,
+    pub undo_review: char,
+    pub suspended: char,
+    pub bury: char,
+    pub suspend: char,
+    pub retire: char,
+    pub unsuspend: char,
+    pub queue_order: char,
+    pub easy: char,
+    pub normal: char,
+    pub hard: char,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            db_path: default_db_path(),
+            new_items_per_day: 5,
+            reviews_per_day: 20,
+            minutes_per_day: None,
+            opener_command: "xdg-open".into(),
+            page_cache_dir: default_page_cache_dir(),
+            timer: TimerConfig::default(),
+            judge: JudgeConfig::default(),
+            scheduling: SchedulingConfig::default(),
+            grading: GradingConfig::default(),
+            backlog: BacklogConfig::default(),
+            queue: QueueConfig::default(),
+            drip: DripConfig::default(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Default for BacklogConfig {
+    fn default() -> Self {
+        Self {
+            recovery: true,
+            spread_days: 0,
+        }
+    }
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            order: QueueOrder::OldestDue,
+            seed: 0,
+        }
+    }
+}
+
+impl Default for DripConfig {
+    fn default() -> Self {
+        Self {
+            items_per_day: 0,
+            balance: DripBalance::Queue,
+        }
+    }
+}
+
+impl Default for TimerConfig {
+    fn default() -> Self {
+        Self {
+            auto_start: false,
+            show_elapsed: true,
+        }
+    }
+}
+
+impl Default for JudgeConfig {
+    fn default() -> Self {
+        Self {
+            solution_dir: ".".into(),
+            time_limit_ms: 2000,
+            comparison: Comparison::Whitespace,
+            float_tolerance: 1e-6,
+            languages: default_languages(),
+        }
+    }
+}
+
+fn default_languages() -> BTreeMap<String, LanguageConfig> {
+    let language = |compile: Option<&str>, run: &str| LanguageConfig {
+        compile: compile.map(String::from),
+        run: run.into(),
+    };
+    BTreeMap::from([
+        (
+            "c".into(),
+            language(Some("gcc -O2 -o {bin} {src} -lm"), "{bin}"),
+        ),
+        (
+            "cpp".into(),
+            language(Some("g++ -O2 -std=c++17 -o {bin} {src}"), "{bin}"),
+        ),
+        (
+            "rs".into(),
+            language(Some("rustc -O -o {bin} {src}"), "{bin}"),
+        ),
+        ("py".into(), language(None, "python3 {src}")),
+    ])
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            review: 'r',
+            promote_new: 'n',
+            stats: 's',
+            sync_feeds: 'f',
+            inbox: 'i',
+            edit_notes: 'e',
+            edit_samples: 't',
+            run_judge: 'x',
+            attach_solution: 'a',
+            undo_review: 'u',
+            suspended: 'h',
+            bury: 'b',
+            suspend: 'z',
+            retire: 'm',
+            unsuspend: 'g',
+            queue_order: 'o',
+            easy: '1',
+            normal: '2',
+            hard: '3',
+        }
+    }
+}
+
+impl KeyBindings {
+    fn all(&self) -> [(&'static str, char); 19] {
+        [
+            ("review", self.review),
+            ("promote_new", self.promote_new),
+            ("stats", self.stats),
+            ("sync_feeds", self.sync_feeds),
+            ("inbox", self.inbox),
+            ("edit_notes", self.edit_notes),
+            ("edit_samples", self.edit_samples),
+            ("run_judge", self.run_judge),
+            ("attach_solution", self.attach_solution),
+            ("undo_review", self.undo_review),
+            ("suspended", self.suspended),
+            ("bury", self.bury),
+            ("suspend", self.suspend),
+            ("retire", self.retire),
+            ("unsuspend", self.unsuspend),
+            ("queue_order", self.queue_order),
+            ("easy", self.easy),
+            ("normal", self.normal),
+            ("hard", self.hard),
+        ]
+    }
+}
+
+/// the value of the xdg base directory variable {xdg_value}, or {home}/{fallback} when it is unset
+/// or relative (which the spec tells us to ignore)
+fn xdg_dir(xdg_value: Option<PathBuf>, home: Option<PathBuf>, fallback: &str) -> Option<PathBuf> {
+    match xdg_value {
+        Some(dir) if dir.is_absolute() => Some(dir),
+        _ => home.map(|home| home.join(fallback)),
+    }
+}
+
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).map(PathBuf::from)
+}
+
+/// where the config file is looked for
+pub fn config_path() -> Option<PathBuf> {
+    xdg_dir(env_path("XDG_CONFIG_HOME"), env_path("HOME"), ".config")
+        .map(|dir| dir.join(APP_DIR).join("config.toml"))
+}
+
+/// $XDG_DATA_HOME/spaced-programming/items.db, or a database in the working directory if we
+/// can't even find a home directory
+pub fn default_db_path() -> PathBuf {
+    xdg_dir(env_path("XDG_DATA_HOME"), env_path("HOME"), ".local/share")
+        .map(|dir| dir.join(APP_DIR).join("items.db"))
+        .unwrap_or_else(|| "./database/items.db".into())
+}
+
+/// $XDG_CACHE_HOME/spaced-programming/pages, next to the database if we can't find a home
+pub fn default_page_cache_dir() -> PathBuf {
+    xdg_dir(env_path("XDG_CACHE_HOME"), env_path("HOME"), ".cache")
+        .map(|dir| dir.join(APP_DIR).join("pages"))
+        .unwrap_or_else(|| "./database/pages".into())
+}
+
+/// reads the config file if there is one, the defaults otherwise
+pub fn load_config() -> AppResult<AppConfig> {
+    match config_path() {
+        Some(path) if path.exists() => read_config(&path),
+        _ => Ok(AppConfig::default()),
+    }
+}
+
+fn read_config(path: &Path) -> AppResult<AppConfig> {
+    let text = std::fs::read_to_string(path).map_err(|err| {
+        AppError::ConfigError(format!("could not read {}: {err}", path.display()))
+    })?;
+    parse_config(&text).map_err(|err| AppError::ConfigError(format!("{}: {err}", path.display())))
+}
+
+/// parses and validates the contents of a config file
+pub fn parse_config(text: &str) -> Result<AppConfig, String> {
+    let mut config: AppConfig = toml::from_str(text).map_err(|err| err.to_string())?;
+    for (extension, language) in default_languages() {
+        config.judge.languages.entry(extension).or_insert(language);
+    }
+    validate(&config)?;
+    Ok(config)
+}
+
+fn validate(config: &AppConfig) -> Result<(), String> {
+    if config.db_path.as_os_str().is_empty() {
+        return Err("db_path can't be empty".into());
+    }
+    if config.opener_command.trim().is_empty() {
+        return Err("opener_command can't be empty".into());
+    }
+    if config.minutes_per_day == Some(0) {
+        return Err("minutes_per_day must be positive".into());
+    }
+    if !(0.0..1.0).contains(&config.scheduling.fuzz) {
+        return Err("scheduling.fuzz must be at least 0 and less than 1".into());
+    }
+    if config.grading.target_minutes == 0 {
+        return Err("grading.target_minutes must be positive".into());
+    }
+    if config.judge.time_limit_ms == 0 {
+        return Err("judge.time_limit_ms must be positive".into());
+    }
+    if config.judge.float_tolerance.is_nan() || config.judge.float_tolerance < 0.0 {
+        return Err("judge.float_tolerance can't be negative".into());
+    }
+    for (extension, language) in &config.judge.languages {
+        if language.run.trim().is_empty() {
+            return Err(format!("judge.languages.{extension}.run can't be empty"));
+        }
+        // languages that don't need building leave compile out instead
+        if language
+            .compile
+            .as_ref()
+            .is_some_and(|compile| compile.trim().is_empty())
+        {
+            return Err(format!(
+                "judge.languages.{extension}.compile can't be empty"
+            ));
+        }
+    }
+    let bindings = config.key_bindings.all();
+    for (i, (action, key)) in bindings.iter().enumerate() {
+        if key.is_whitespace() || key.is_control() {
+            return Err(format!("key_bindings.{action} must be a visible character"));
+        }
+        if let Some((other, _)) = bindings[..i].iter().find(|(_, other)| other == key) {
+            return Err(format!(
+                "key_bindings.{action} and key_bindings.{other} are both bound to '{key}'"
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_config_is_default() {
+        assert!(parse_config("") == Ok(AppConfig::default()));
+    }
+
+    #[test]
+    fn partial_config_keeps_other_defaults() {
+        let config = parse_config(
+            "db_path = \"/tmp/items.db\"\n\
+             reviews_per_day = 50\n\
+             opener_command = \"firefox --new-tab\"\n\
+             [timer]\n\
+             show_elapsed = false\n\
+             [key_bindings]\n\
+             easy = 'j'\n",
+        )
+        .unwrap();
+        assert!(config.db_path == Path::new("/tmp/items.db"));
+        assert!(config.reviews_per_day == 50);
+        assert!(config.new_items_per_day == AppConfig::default().new_items_per_day);
+        assert!(config.minutes_per_day.is_none());
+        assert!(config.opener_command == "firefox --new-tab");
+        assert!(!config.timer.show_elapsed);
+        assert!(!config.timer.auto_start);
+        assert!(config.key_bindings.easy == 'j');
+        assert!(config.key_bindings.hard == '3');
+    }
+
+    #[test]
+    fn judge_languages_extend_defaults() {
+        let config = parse_config(
+            "[judge]\n\
+             comparison = \"float\"\n\
+             [judge.languages.go]\n\
+             run = \"go run {src}\"\n\
+             [judge.languages.py]\n\
+             run = \"pypy3 {src}\"\n",
+        )
+        .unwrap();
+        assert!(config.judge.comparison == Comparison::Float);
+        assert!(config.judge.languages["go"].run == "go run {src}");
+        assert!(config.judge.languages["py"].run == "pypy3 {src}");
+        assert!(config.judge.languages["cpp"] == default_languages()["cpp"]);
+        assert!(parse_config("[judge]\ncomparison = \"fuzzy\"").is_err());
+        assert!(parse_config("[judge]\ntime_limit_ms = 0").is_err());
+        assert!(parse_config("[judge.languages.go]\nrun = \"\"").is_err());
+    }
+
+    #[test]
+    fn scheduling_per_tag() {
+        let config = parse_config(
+            "[scheduling]\n\
+             algorithm = \"fsrs\"\n\
+             [scheduling.tags]\n\
+             graphs = \"leitner\"\n\
+             dp = \"sm2\"\n",
+        )
+        .unwrap();
+        let scheduling = &config.scheduling;
+        assert!(scheduling.algorithm_for(&[]) == Algorithm::Fsrs);
+        assert!(scheduling.algorithm_for(&["graphs".into()]) == Algorithm::Leitner);
+        // dp comes before graphs
+        assert!(scheduling.algorithm_for(&["graphs".into(), "dp".into()]) == Algorithm::Sm2);
+        assert!(AppConfig::default().scheduling.algorithm == Algorithm::SpacedRs);
+        assert!(parse_config("[scheduling]\nalgorithm = \"sm5\"").is_err());
+        assert!(parse_config("[scheduling]\nfuzz = 1.5").is_err());
+        let config = parse_config("[queue]\norder = \"tag-round-robin\"\nseed = 7").unwrap();
+        assert!(config.queue.order == QueueOrder::TagRoundRobin && config.queue.seed == 7);
+        assert!(parse_config("[queue]\norder = \"newest\"").is_err());
+        assert!(parse_config("[grading]\ntarget_minutes = 0").is_err());
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        // typos should not be silently ignored
+        assert!(parse_config("review_per_day = 3").is_err());
+        assert!(parse_config("reviews_per_day = -3").is_err());
+        assert!(parse_config("minutes_per_day = 0").is_err());
+        assert!(parse_config("[drip]\nbalance = \"feeds\"").is_err());
+        assert!(parse_config("opener_command = \" \"").is_err());
+        assert!(parse_config("[judge.languages.go]\nrun = \"\"").is_err());
+        let err = parse_config("[judge.languages.go]\ncompile = \"  \"\nrun = \"{bin}\"");
+        assert!(err.unwrap_err() == "judge.languages.go.compile can't be empty");
+        assert!(parse_config("[key_bindings]\neasy = ' '").is_err());
+        let err = parse_config("[key_bindings]\neasy = 'r'").unwrap_err();
+        assert!(err == "key_bindings.easy and key_bindings.review are both bound to 'r'");
+        let err = parse_config("[key_bindings]\nunsuspend = 'a'").unwrap_err();
+        assert!(
+            err == "key_bindings.unsuspend and key_bindings.attach_solution are both bound to 'a'"
+        );
+    }
+
+    #[test]
+    fn xdg_dirs() {
+        let home = Some(PathBuf::from("/home/chris"));
+        let xdg = Some(PathBuf::from("/xdg/config"));
+        assert!(xdg_dir(xdg, home.clone(), ".config") == Some("/xdg/config".into()));
+        // relative values are invalid according to the spec
+        let relative = Some(PathBuf::from("config"));
+        assert!(xdg_dir(relative, home.clone(), ".config") == Some("/home/chris/.config".into()));
+        assert!(xdg_dir(None, home, ".local/share") == Some("/home/chris/.local/share".into()));
+        assert!(xdg_dir(None, None, ".config").is_none());
+    }
+}
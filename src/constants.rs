@@ -1,3 +1,8 @@
 pub const BANNER_STR: &str = "╔═╗╦═╗╔═╗╔═╗╦═╗╔═╗╔╦╗╔═╗╔╦╗╦╔═╗╔═╗\n\
                               ╠═╝╠╦╝║ ║║ ╦╠╦╝╠═╣║║║╠═╣ ║ ║║  ╠═╣\n\
                               ╩  ╩╚═╚═╝╚═╝╩╚═╩ ╩╩ ╩╩ ╩ ╩ ╩╚═╝╩ ╩";
+
+/// how many weeks of reviews the heatmap on the stats screen covers
+pub const HEATMAP_WEEKS: u64 = 26;
+/// how many days ahead the due forecast on the stats screen looks
+pub const FORECAST_DAYS: usize = 30;
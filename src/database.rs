@@ -1,65 +1,147 @@
 //! This module contains all interactions with the database. I here expose sql calls through functions and nothing else.
 
 // stdlib imports
+use std::collections::HashMap;
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 // external imports
-use rusqlite::{Connection, Row};
-use spaced_rs::SchedulingData;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use spaced_rs::{SchedulingData, UserReview};
 // internal imports
 use crate::{
+    config::SchedulingConfig,
+    constants::{FORECAST_DAYS, HEATMAP_WEEKS},
     error::{DatabaseErrorSource, DatabaseResult},
-    types::{Item, ItemId, URLItem},
+    scheduling::{self, SECONDS_PER_DAY},
+    types::{
+        DoneToday, DripPromotion, Feed, FeedEntry, FeedId, InboxReport, Item, ItemId, ItemStatus,
+        NoteRevision, ProblemMetadata, ReviewId, ReviewLog, SampleTest, SchedulerState,
+        SchemaVersion, Solution, Stats, SuspendedItem, URLItem, Verdict,
+    },
+    urls::canonicalize_url,
 };
 
-const SCHEMA_VERSION: usize = 3;
+/// A single step in the evolution of our schema. Step i in [MIGRATIONS] takes a database from
+/// user_version i to user_version i + 1.
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+/// Every schema change we have ever made, oldest first. Never edit or reorder a step that has
+/// been released, append a new one instead (and a fixture for the schema it migrates from).
+const MIGRATIONS: &[Migration] = &[
+    // 0 -> 1: items and inbox tables
+    init_schema,
+    // 1 -> 2: tags shared by items and inbox entries
+    create_tag_tables,
+    // 2 -> 3: markdown notes per item and their history
+    create_notes,
+    // 3 -> 4: a log of every graded review
+    create_reviews_table,
+    // 4 -> 5: rss/atom feeds and the entries we have already seen
+    create_feed_tables,
+    // 5 -> 6: every url is in the inbox at most once
+    dedupe_inbox,
+    // 6 -> 7: the inbox is ordered by an explicit position
+    add_inbox_positions,
+    // 7 -> 8: problem metadata read off the url and the page cache
+    add_problem_metadata,
+    // 8 -> 9: sample tests for the local judge and its verdict in the review log
+    create_sample_tests,
+    // 9 -> 10: the solutions we wrote, attached to the review they were written for
+    create_solutions_table,
+    // 10 -> 11: the state of the scheduling algorithm that last scheduled an item
+    add_scheduler_state,
+    // 11 -> 12: a target time per item, used to suggest grades
+    add_target_times,
+    // 12 -> 13: items can be suspended, buried or retired
+    add_item_status,
+    // 13 -> 14: a log of the inbox entries promoted by the daily drip
+    create_drip_tables,
+    // 14 -> 15: when items were last reviewed and the days we spread the backlog
+    add_last_review,
+];
+
+/// the user_version of a database with every migration applied
+pub fn latest_schema_version() -> SchemaVersion {
+    MIGRATIONS.len()
+}
 
 /// creates a connection to the database at location {path} and creates the paths leading up to it if id didn't exist
+/// Databases created with an older schema are backed up and migrated to the latest one.
 pub fn open_connection(path: &Path) -> DatabaseResult<Connection> {
     // check if the db already exists
-    let conn;
-    if path.exists() {
-        // if it exists that means we have initialized it before, therefore the schema should be valid.
-        // we now need to validate it's schema... before we can return the connnection
-        conn = Connection::open(path)?;
-        let sv = schema_version(&conn)?;
-        if sv != SCHEMA_VERSION {
-            // if the schema was invalid we simply err, might have some migration logic in the future
-            return Err(DatabaseErrorSource::InvalidSchemaError(sv));
-        }
-    } else {
+    let existed = path.exists();
+    if !existed {
         let parent = path
             .parent()
             .expect("could not retrieve parent! The provided path cannot be a valid location");
         std::fs::create_dir_all(parent)?;
-        conn = Connection::open(path)?;
-        init_schema(&conn)?;
+    }
+    let mut conn = Connection::open(path)?;
+    // sqlite only enforces our REFERENCES clauses (and their cascades) when asked to
+    conn.pragma_update(None, "foreign_keys", true)?;
+    let version = schema_version(&conn)?;
+    if version > latest_schema_version() {
+        // created by a newer version of the application, we don't know how to downgrade it
+        return Err(DatabaseErrorSource::InvalidSchemaError(version));
+    }
+    if version < latest_schema_version() {
+        // a fresh database has nothing worth backing up
+        if existed {
+            backup_database(path, version)?;
+        }
+        run_migrations(&mut conn, MIGRATIONS)?;
     }
     Ok(conn)
 }
 
-pub fn schema_version(conn: &Connection) -> DatabaseResult<usize> {
-    let query = "SELECT schema_version FROM pragma_schema_version";
+/// the schema version as stored in the user_version pragma (0 for a fresh database)
+pub fn schema_version(conn: &Connection) -> DatabaseResult<SchemaVersion> {
+    let query = "SELECT user_version FROM pragma_user_version";
 
     let mut stmt = conn.prepare(query)?;
     Ok(stmt.query_row([], |row: &Row<'_>| {
-        let schema_version: usize = row.get(0)?;
+        let schema_version: SchemaVersion = row.get(0)?;
         Ok(schema_version)
     })?)
 }
 
-pub fn init_schema(conn: &Connection) -> DatabaseResult<()> {
+/// copies the database file to {path}.v{version}.bak before we start altering its schema
+fn backup_database(path: &Path, version: SchemaVersion) -> DatabaseResult<()> {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(format!(".v{version}.bak"));
+    std::fs::copy(path, backup_path).map_err(DatabaseErrorSource::BackupError)?;
+    Ok(())
+}
+
+/// applies every migration the database has not seen yet. All steps share one transaction, so a
+/// failing step leaves the database exactly as it was before we opened it.
+fn run_migrations(conn: &mut Connection, migrations: &[Migration]) -> DatabaseResult<()> {
+    let current = schema_version(conn)?;
+    let tx = conn.transaction()?;
+    for (from, migration) in migrations.iter().enumerate().skip(current) {
+        let to = from + 1;
+        migration(&tx)
+            .and_then(|_| tx.pragma_update(None, "user_version", to))
+            .map_err(|source| DatabaseErrorSource::MigrationError { from, to, source })?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
     // create items table (containing item specific data)
     // create schedule table (used to assign due dates and query items that are due)
     // create inbox table (used to store urls+tags for future items)
-    create_items_table(&conn)?;
-    create_inbox_table(&conn)?;
+    create_items_table(conn)?;
+    create_inbox_table(conn)?;
     Ok(())
 }
 
-fn create_items_table(conn: &Connection) -> DatabaseResult<()> {
+// databases created before we tracked user_version already contain these tables, hence IF NOT EXISTS
+fn create_items_table(conn: &Connection) -> rusqlite::Result<()> {
     // the item table
     // the id column is an alias for the rowid
-    let sql_string = "CREATE TABLE items (\
+    let sql_string = "CREATE TABLE IF NOT EXISTS items (\
                 id INTEGER PRIMARY KEY AUTOINCREMENT,\
                 interval INTEGER NOT NULL,\
                 difficulty REAL NOT NULL,\
@@ -68,13 +150,13 @@ fn create_items_table(conn: &Connection) -> DatabaseResult<()> {
                 times_reviewed INTEGER NOT NULL,\
                 times_recalled INTEGER NOT NULL,\
                 due INTEGER NOT NULL,\
-                url TEXT NOT NULL UNIQUE,\
+                url TEXT NOT NULL UNIQUE\
             )";
     conn.execute(sql_string, [])?;
     Ok(())
 }
-fn create_inbox_table(conn: &Connection) -> DatabaseResult<()> {
-    let sql_string = "CREATE TABLE inbox (\
+fn create_inbox_table(conn: &Connection) -> rusqlite::Result<()> {
+    let sql_string = "CREATE TABLE IF NOT EXISTS inbox (\
                 id INTEGER PRIMARY KEY AUTOINCREMENT,\
                 url TEXT NOT NULL\
             )";
@@ -82,30 +164,280 @@ fn create_inbox_table(conn: &Connection) -> DatabaseResult<()> {
     Ok(())
 }
 
-/// adds a new urls to the bottom inbox table. (enqueue)
-pub fn add_urls_to_inbox(conn: &Connection, new_items: Vec<String>) -> DatabaseResult<()> {
-    let stmt = "INSERT INTO inbox (url) VALUES (?)";
-    for url in new_items {
-        conn.execute(stmt, [url])?;
+fn create_tag_tables(conn: &Connection) -> rusqlite::Result<()> {
+    // tag names are unique, items and inbox entries refer to them through the join tables below
+    let sql_string = "CREATE TABLE tags (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                name TEXT NOT NULL UNIQUE\
+            );\
+            CREATE TABLE item_tags (\
+                item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,\
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,\
+                PRIMARY KEY (item_id, tag_id)\
+            );\
+            CREATE TABLE inbox_tags (\
+                inbox_id INTEGER NOT NULL REFERENCES inbox(id) ON DELETE CASCADE,\
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,\
+                PRIMARY KEY (inbox_id, tag_id)\
+            );";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn create_notes(conn: &Connection) -> rusqlite::Result<()> {
+    // the current notes live on the item, every saved version is kept in note_history
+    let sql_string = "ALTER TABLE items ADD COLUMN item_notes TEXT NOT NULL DEFAULT '';\
+            CREATE TABLE note_history (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,\
+                notes TEXT NOT NULL,\
+                timestamp INTEGER NOT NULL\
+            );";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn create_reviews_table(conn: &Connection) -> rusqlite::Result<()> {
+    // grade is 1 (easy), 2 (normal) or 3 (hard), duration is in milliseconds and NULL when the
+    // review was graded without running the timer
+    let sql_string = "CREATE TABLE reviews (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,\
+                timestamp INTEGER NOT NULL,\
+                grade INTEGER NOT NULL,\
+                duration INTEGER,\
+                interval_before INTEGER NOT NULL,\
+                interval_after INTEGER NOT NULL,\
+                difficulty_before REAL NOT NULL,\
+                difficulty_after REAL NOT NULL\
+            );\
+            CREATE INDEX reviews_item_id ON reviews (item_id);";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn create_feed_tables(conn: &Connection) -> rusqlite::Result<()> {
+    // feed_entries remembers every guid we have enqueued, even after the inbox entry was promoted
+    // or removed, so a sync never adds the same problem twice
+    let sql_string = "CREATE TABLE feeds (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                url TEXT NOT NULL UNIQUE,\
+                last_synced INTEGER\
+            );\
+            CREATE TABLE feed_entries (\
+                feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,\
+                guid TEXT NOT NULL,\
+                PRIMARY KEY (feed_id, guid)\
+            );";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn dedupe_inbox(conn: &Connection) -> rusqlite::Result<()> {
+    // urls that were promoted or added twice would only fail later on promote, the oldest entry
+    // of each url keeps its place in the queue
+    let sql_string = "DELETE FROM inbox WHERE url IN (SELECT url FROM items);\
+            DELETE FROM inbox WHERE id NOT IN (SELECT MIN(id) FROM inbox GROUP BY url);\
+            CREATE UNIQUE INDEX inbox_url ON inbox (url);";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn add_inbox_positions(conn: &Connection) -> rusqlite::Result<()> {
+    // until now the queue was in insertion order
+    let sql_string = "ALTER TABLE inbox ADD COLUMN position INTEGER NOT NULL DEFAULT 0;\
+            UPDATE inbox SET position = id;";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn add_problem_metadata(conn: &Connection) -> rusqlite::Result<()> {
+    // all NULL for items from sites we don't know, title and difficulty also until the problem
+    // page is in the page cache
+    let sql_string = "ALTER TABLE items ADD COLUMN site TEXT;\
+            ALTER TABLE items ADD COLUMN problem_id TEXT;\
+            ALTER TABLE items ADD COLUMN contest TEXT;\
+            ALTER TABLE items ADD COLUMN problem_name TEXT;\
+            ALTER TABLE items ADD COLUMN title TEXT;\
+            ALTER TABLE items ADD COLUMN problem_difficulty TEXT;";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn create_sample_tests(conn: &Connection) -> rusqlite::Result<()> {
+    // verdict is one of AC, WA, TLE, RE or CE, NULL when the judge was not run
+    let sql_string = "CREATE TABLE sample_tests (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,\
+                input TEXT NOT NULL,\
+                output TEXT NOT NULL\
+            );\
+            CREATE INDEX sample_tests_item_id ON sample_tests (item_id);\
+            ALTER TABLE reviews ADD COLUMN verdict TEXT;";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn create_solutions_table(conn: &Connection) -> rusqlite::Result<()> {
+    // language is the file extension of the solution, e.g. "cpp"
+    let sql_string = "CREATE TABLE solutions (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,\
+                review_id INTEGER REFERENCES reviews(id),\
+                language TEXT NOT NULL,\
+                source TEXT NOT NULL,\
+                timestamp INTEGER NOT NULL\
+            );\
+            CREATE INDEX solutions_item_id ON solutions (item_id);";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn add_scheduler_state(conn: &Connection) -> rusqlite::Result<()> {
+    // json, NULL for items scheduled by spaced-rs which keeps its state in the other columns
+    conn.execute_batch("ALTER TABLE items ADD COLUMN scheduler_state TEXT;")?;
+    Ok(())
+}
+
+fn add_target_times(conn: &Connection) -> rusqlite::Result<()> {
+    // in seconds, NULL for items that go by the target for their difficulty
+    conn.execute_batch("ALTER TABLE items ADD COLUMN target_secs INTEGER;")?;
+    Ok(())
+}
+
+fn add_item_status(conn: &Connection) -> rusqlite::Result<()> {
+    // buried_until is only set for buried items, they take part in reviews again from then on
+    let sql_string = "ALTER TABLE items ADD COLUMN status TEXT NOT NULL DEFAULT 'active';\
+            ALTER TABLE items ADD COLUMN buried_until INTEGER;";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn create_drip_tables(conn: &Connection) -> rusqlite::Result<()> {
+    // a row in drips for every day the drip ran, even if the inbox was empty, so it runs at most
+    // once a day
+    let sql_string = "CREATE TABLE drips (\
+                day INTEGER PRIMARY KEY,\
+                timestamp INTEGER NOT NULL\
+            );\
+            CREATE TABLE drip_promotions (\
+                day INTEGER NOT NULL REFERENCES drips(day),\
+                item_id INTEGER REFERENCES items(id) ON DELETE SET NULL,\
+                url TEXT NOT NULL\
+            );";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+fn add_last_review(conn: &Connection) -> rusqlite::Result<()> {
+    // reviews from before the review log are dated back from the due date they set, that is the
+    // best we know about them
+    let sql_string = "ALTER TABLE items ADD COLUMN last_review INTEGER;\
+            UPDATE items SET last_review = \
+                (SELECT MAX(timestamp) FROM reviews WHERE reviews.item_id = items.id);\
+            UPDATE items SET last_review = MAX(due - MAX(interval, 1) * 86400, 0) \
+                WHERE last_review IS NULL AND times_reviewed > 0;\
+            CREATE TABLE backlog_spreads (\
+                day INTEGER PRIMARY KEY,\
+                timestamp INTEGER NOT NULL\
+            );";
+    conn.execute_batch(sql_string)?;
+    Ok(())
+}
+
+/// adds the canonical form of {url} to the bottom of the inbox unless it is already in the inbox
+/// or an item, and records which of the three happened in {report}
+fn enqueue_url(conn: &Connection, url: &str, report: &mut InboxReport) -> DatabaseResult<()> {
+    let canonical = canonicalize_url(url);
+    // urls added before we canonicalized them are stored as they were entered
+    let exists = |table: &str| -> rusqlite::Result<bool> {
+        let query = format!("SELECT EXISTS(SELECT 1 FROM {table} WHERE url IN (?, ?))");
+        conn.query_row(&query, params![url.trim(), canonical], |row| row.get(0))
+    };
+    if exists("items")? {
+        report.already_items.push(canonical);
+    } else if exists("inbox")? {
+        report.already_in_inbox.push(canonical);
+    } else {
+        let stmt = "INSERT INTO inbox (url, position) \
+                    VALUES (?, (SELECT IFNULL(MAX(position), 0) + 1 FROM inbox))";
+        conn.execute(stmt, [&canonical])?;
+        report.added.push(canonical);
     }
     Ok(())
 }
 
+/// adds new urls to the bottom inbox table (enqueue). Urls are canonicalized first, and skipped
+/// if we already have them in the inbox or as an item.
+pub fn add_urls_to_inbox(conn: &Connection, new_items: Vec<String>) -> DatabaseResult<InboxReport> {
+    let mut report = InboxReport::default();
+    for url in new_items {
+        enqueue_url(conn, &url, &mut report)?;
+    }
+    Ok(report)
+}
+
 /// gets the top n items in the queue
 pub fn get_n_urls_from_inbox(conn: &Connection, n_items: usize) -> DatabaseResult<Vec<URLItem>> {
-    let query = "SELECT id, url FROM inbox LIMIT ?";
+    let query = "SELECT id, url FROM inbox ORDER BY position, id LIMIT ?";
     let mut smts = conn.prepare(query)?;
-    let rows = smts
+    let rows: Vec<URLItem> = smts
         .query_map([n_items], |row| {
             Ok(URLItem {
                 id: row.get(0)?,
                 url: row.get(1)?,
+                tags: vec![],
             })
         })?
-        .into_iter()
         .map(|row| row.expect("Could not create NewItem from query!"))
         .collect();
-    Ok(rows)
+    rows.into_iter()
+        .map(|mut url_item| {
+            url_item.tags = get_inbox_tags(conn, url_item.id)?;
+            Ok(url_item)
+        })
+        .collect()
+}
+
+/// moves inbox entry {inbox_id} to index {position} of the queue (0 is the top), positions past the
+/// end of the queue move it to the bottom
+pub fn move_inbox_item(
+    conn: &mut Connection,
+    inbox_id: u64,
+    position: usize,
+) -> DatabaseResult<()> {
+    let tx = conn.transaction()?;
+    let mut ids = tx
+        .prepare("SELECT id FROM inbox ORDER BY position, id")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<u64>>>()?;
+    let from = ids
+        .iter()
+        .position(|id| *id == inbox_id)
+        .ok_or(DatabaseErrorSource::SQLError(
+            rusqlite::Error::QueryReturnedNoRows,
+        ))?;
+    ids.remove(from);
+    ids.insert(position.min(ids.len()), inbox_id);
+    // renumbering the whole queue keeps the positions dense
+    for (position, id) in ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE inbox SET position=? WHERE id=?",
+            params![position + 1, id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// moves inbox entry {inbox_id} to the top of the queue, it is the next one to be promoted
+pub fn move_inbox_item_to_top(conn: &mut Connection, inbox_id: u64) -> DatabaseResult<()> {
+    move_inbox_item(conn, inbox_id, 0)
+}
+
+/// moves inbox entry {inbox_id} to the bottom of the queue, behind everything we have added
+pub fn move_inbox_item_to_bottom(conn: &mut Connection, inbox_id: u64) -> DatabaseResult<()> {
+    move_inbox_item(conn, inbox_id, usize::MAX)
 }
 
 /// removes item from the inbox (probably to turn it into a review item)
@@ -115,153 +447,1080 @@ pub fn remove_new_item(conn: &Connection, id: u64) -> DatabaseResult<()> {
     Ok(())
 }
 
-// gets the item ids whose due date value is less than timestamp
-// TODO the id field in the DueItem will be redundant but we return it anyway.
-pub fn get_due_items(conn: &Connection, timestamp: u64) -> DatabaseResult<Vec<Item>> {
-    let query = "SELECT  id, interval, difficulty, memory_strength, adjusting_factor, times_reviewed, times_recalled, due, url, tags, item_notes FROM items WHERE due <= ?";
+/// adds a feed to sync new problems from
+pub fn add_feed(conn: &Connection, url: &str) -> DatabaseResult<FeedId> {
+    conn.execute("INSERT INTO feeds (url) VALUES (?)", [url])?;
+    Ok(conn.last_insert_rowid() as FeedId)
+}
+
+/// every feed, in the order they were added
+pub fn get_feeds(conn: &Connection) -> DatabaseResult<Vec<Feed>> {
+    let query = "SELECT id, url, last_synced FROM feeds ORDER BY id";
     let mut stmt = conn.prepare(query)?;
+    let feeds = stmt
+        .query_map([], |row| {
+            Ok(Feed {
+                id: row.get(0)?,
+                url: row.get(1)?,
+                last_synced: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<Feed>>>()?;
+    Ok(feeds)
+}
+
+/// removes a feed and forgets which of its entries we have seen. Problems it already added stay
+/// in the inbox.
+pub fn remove_feed(conn: &Connection, id: FeedId) -> DatabaseResult<()> {
+    conn.execute("DELETE FROM feeds WHERE id=?", [id])?;
+    Ok(())
+}
+
+/// enqueues the links of the entries we have not seen before and marks the feed as synced at
+/// {timestamp}. Returns the number of urls added to the inbox.
+pub fn add_feed_entries(
+    conn: &mut Connection,
+    feed_id: FeedId,
+    entries: &[FeedEntry],
+    timestamp: u64,
+) -> DatabaseResult<usize> {
+    let tx = conn.transaction()?;
+    let mut report = InboxReport::default();
+    for entry in entries {
+        let new_entry = tx.execute(
+            "INSERT OR IGNORE INTO feed_entries (feed_id, guid) VALUES (?, ?)",
+            params![feed_id, entry.guid],
+        )?;
+        if new_entry == 1 {
+            enqueue_url(&tx, &entry.link, &mut report)?;
+        }
+    }
+    let updated = tx.execute(
+        "UPDATE feeds SET last_synced=? WHERE id=?",
+        params![timestamp, feed_id],
+    )?;
+    if updated == 0 {
+        return Err(DatabaseErrorSource::SQLError(
+            rusqlite::Error::QueryReturnedNoRows,
+        ));
+    }
+    tx.commit()?;
+    Ok(report.added.len())
+}
+
+/// the columns item_from_row expects, in order
+const ITEM_COLUMNS: &str = "items.id, interval, difficulty, memory_strength, adjusting_factor, \
+                times_reviewed, times_recalled, due, url, item_notes, \
+                site, problem_id, contest, problem_name, title, problem_difficulty, scheduler_state, \
+                target_secs, last_review";
+
+/// builds an Item from a row that starts with the [ITEM_COLUMNS]. Tags live in their own table
+/// and are left empty, see [with_tags].
+fn item_from_row(row: &Row<'_>) -> rusqlite::Result<Item> {
+    Ok(Item {
+        id: row.get(0)?,
+        scheduling_data: SchedulingData {
+            interval: row.get(1)?,
+            difficulty: row.get(2)?,
+            memory_strength: row.get(3)?,
+            adjusting_factor: row.get(4)?,
+            times_reviewed: row.get(5)?,
+            times_recalled: row.get(6)?,
+        },
+        due: row.get(7)?,
+        last_review: row.get(18)?,
+        url: row.get(8)?,
+        tags: vec![],
+        notes: row.get(9)?,
+        metadata: metadata_from_row(row, 10)?,
+        scheduler_state: scheduler_state_from_sql(row.get(16)?)?,
+        target: row.get::<_, Option<u64>>(17)?.map(Duration::from_secs),
+    })
+}
+
+fn scheduler_state_to_sql(state: &SchedulerState) -> Option<String> {
+    match state {
+        SchedulerState::SpacedRs => None,
+        state => Some(serde_json::to_string(state).expect("scheduler states serialize")),
+    }
+}
+
+fn scheduler_state_from_sql(json: Option<String>) -> rusqlite::Result<SchedulerState> {
+    match json {
+        None => Ok(SchedulerState::SpacedRs),
+        Some(json) => serde_json::from_str(&json).map_err(|err| {
+            rusqlite::Error::FromSqlConversionFailure(16, rusqlite::types::Type::Text, err.into())
+        }),
+    }
+}
+
+/// the metadata in the six columns starting at {first}, None if the item has none
+fn metadata_from_row(row: &Row<'_>, first: usize) -> rusqlite::Result<Option<ProblemMetadata>> {
+    let site: Option<String> = row.get(first)?;
+    let Some(site) = site else {
+        return Ok(None);
+    };
+    Ok(Some(ProblemMetadata {
+        site,
+        problem_id: row.get(first + 1)?,
+        contest: row.get(first + 2)?,
+        name: row.get(first + 3)?,
+        title: row.get(first + 4)?,
+        difficulty: row.get(first + 5)?,
+    }))
+}
+
+/// replaces the problem metadata of item {item_id}
+pub fn set_item_metadata(
+    conn: &Connection,
+    item_id: ItemId,
+    metadata: Option<&ProblemMetadata>,
+) -> DatabaseResult<()> {
+    let stmt = "UPDATE items SET site=?, problem_id=?, contest=?, problem_name=?, title=?, \
+                problem_difficulty=? WHERE id=?";
+    let updated = conn.execute(
+        stmt,
+        params![
+            metadata.map(|m| &m.site),
+            metadata.map(|m| &m.problem_id),
+            metadata.and_then(|m| m.contest.as_ref()),
+            metadata.map(|m| &m.name),
+            metadata.and_then(|m| m.title.as_ref()),
+            metadata.and_then(|m| m.difficulty.as_ref()),
+            item_id,
+        ],
+    )?;
+    if updated == 0 {
+        return Err(DatabaseErrorSource::SQLError(
+            rusqlite::Error::QueryReturnedNoRows,
+        ));
+    }
+    Ok(())
+}
+
+/// sets the target time of item {item_id}, None makes it go by its difficulty again
+pub fn set_item_target(
+    conn: &Connection,
+    item_id: ItemId,
+    target: Option<Duration>,
+) -> DatabaseResult<()> {
+    let stmt = "UPDATE items SET target_secs=? WHERE id=?";
+    let updated = conn.execute(stmt, params![target.map(|t| t.as_secs()), item_id])?;
+    if updated == 0 {
+        return Err(DatabaseErrorSource::SQLError(
+            rusqlite::Error::QueryReturnedNoRows,
+        ));
+    }
+    Ok(())
+}
+
+/// the condition for an item to take part in reviews at the timestamp bound to ?1
+const REVIEWABLE: &str = "(status = 'active' OR (status = 'buried' AND buried_until <= ?1))";
+
+fn status_from_sql(status: String, buried_until: Option<u64>) -> rusqlite::Result<ItemStatus> {
+    match (status.as_str(), buried_until) {
+        ("active", _) => Ok(ItemStatus::Active),
+        ("suspended", _) => Ok(ItemStatus::Suspended),
+        ("buried", Some(until)) => Ok(ItemStatus::Buried(until)),
+        ("retired", _) => Ok(ItemStatus::Retired),
+        _ => Err(rusqlite::Error::InvalidColumnType(
+            2,
+            status,
+            rusqlite::types::Type::Text,
+        )),
+    }
+}
+
+/// changes whether item {item_id} takes part in reviews
+pub fn set_item_status(
+    conn: &Connection,
+    item_id: ItemId,
+    status: ItemStatus,
+) -> DatabaseResult<()> {
+    let buried_until = match status {
+        ItemStatus::Buried(until) => Some(until),
+        _ => None,
+    };
+    let stmt = "UPDATE items SET status=?, buried_until=? WHERE id=?";
+    let updated = conn.execute(stmt, params![status.name(), buried_until, item_id])?;
+    if updated == 0 {
+        return Err(DatabaseErrorSource::SQLError(
+            rusqlite::Error::QueryReturnedNoRows,
+        ));
+    }
+    Ok(())
+}
+
+/// the items that are left out of reviews at {timestamp}, by id. Buried items whose time has come
+/// are not among them.
+pub fn get_suspended_items(
+    conn: &Connection,
+    timestamp: u64,
+) -> DatabaseResult<Vec<SuspendedItem>> {
+    let query = format!(
+        "SELECT id, url, status, buried_until FROM items WHERE NOT {REVIEWABLE} ORDER BY id"
+    );
+    let mut stmt = conn.prepare(&query)?;
     let rows = stmt
         .query_map([timestamp], |row| {
-            Ok(Item {
+            Ok(SuspendedItem {
                 id: row.get(0)?,
-                scheduling_data: SchedulingData {
-                    interval: row.get(1)?,
-                    difficulty: row.get(2)?,
-                    memory_strength: row.get(3)?,
-                    adjusting_factor: row.get(4)?,
-                    times_reviewed: row.get(5)?,
-                    times_recalled: row.get(6)?,
-                },
-                due: row.get(7)?,
-                url: row.get(8)?,
+                url: row.get(1)?,
+                status: status_from_sql(row.get(2)?, row.get(3)?)?,
             })
         })?
-        .map(|res| res.expect("could not build Item from query!"))
+        .map(|res| res.expect("could not build SuspendedItem from query!"))
         .collect();
     Ok(rows)
 }
 
-pub fn update_items(items: Vec<Item>) -> DatabaseResult<()> {
-    todo!();
+/// fills in the tags of items fetched through [item_from_row]
+fn with_tags(conn: &Connection, items: Vec<Item>) -> DatabaseResult<Vec<Item>> {
+    items
+        .into_iter()
+        .map(|mut item| {
+            item.tags = get_item_tags(conn, item.id)?;
+            Ok(item)
+        })
+        .collect()
 }
 
-// sets the columns of a given item row to the fields of our Item instance
-// this should be used to update an existing item row.
-pub fn update_item(item: Item) -> DatabaseResult<()> {
-    update_items(vec![item])
+// gets the item ids whose due date value is less than timestamp, leaving out suspended, buried
+// and retired items
+// TODO the id field in the DueItem will be redundant but we return it anyway.
+pub fn get_due_items(conn: &Connection, timestamp: u64) -> DatabaseResult<Vec<Item>> {
+    let query = format!(
+        "SELECT {ITEM_COLUMNS} FROM items WHERE due <= ?1 AND {REVIEWABLE} ORDER BY due, items.id"
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt
+        .query_map([timestamp], item_from_row)?
+        .map(|res| res.expect("could not build Item from query!"))
+        .collect();
+    with_tags(conn, rows)
 }
 
-// inserts a new item into the items table (when turning a new_item into an item)
-// Should return the id as provided by sqlite (we use this id when scheduling the item)
-pub fn insert_item(item: Item) -> DatabaseResult<ItemId> {
-    todo!()
+/// like [get_due_items] but only returns items tagged with any of (or all of, if {match_all}) the
+/// given tags. An empty tag list does not restrict the result.
+pub fn get_due_items_with_tags(
+    conn: &Connection,
+    timestamp: u64,
+    tags: &[&str],
+    match_all: bool,
+) -> DatabaseResult<Vec<Item>> {
+    let mut names: Vec<String> = tags.iter().filter_map(|tag| normalize_tag(tag)).collect();
+    names.sort();
+    names.dedup();
+    if names.is_empty() {
+        return get_due_items(conn, timestamp);
+    }
+    // an item matches if it carries enough of the requested tags: one of them, or every one
+    let n_required = if match_all { names.len() } else { 1 };
+    let placeholders = vec!["?"; names.len()].join(", ");
+    let query = format!(
+        "SELECT {ITEM_COLUMNS} FROM items WHERE due <= ?1 AND {REVIEWABLE} AND items.id IN (\
+            SELECT item_tags.item_id FROM item_tags JOIN tags ON tags.id = item_tags.tag_id \
+            WHERE tags.name IN ({placeholders}) \
+            GROUP BY item_tags.item_id HAVING COUNT(*) >= ?\
+        ) ORDER BY due, items.id"
+    );
+    let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(timestamp)];
+    for name in names {
+        query_params.push(Box::new(name));
+    }
+    query_params.push(Box::new(n_required));
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt
+        .query_map(params_from_iter(query_params), item_from_row)?
+        .map(|res| res.expect("could not build Item from query!"))
+        .collect();
+    with_tags(conn, rows)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serial_test::serial;
-    use std::path::PathBuf;
+/// the number of items that are due at {timestamp}
+pub fn count_due_items(conn: &Connection, timestamp: u64) -> DatabaseResult<u32> {
+    let query = format!("SELECT COUNT(*) FROM items WHERE due <= ?1 AND {REVIEWABLE}");
+    Ok(conn.query_row(&query, [timestamp], |row| row.get(0))?)
+}
 
-    /// util function to create temp path for db_instance. Returns path and cleanup function.
-    fn create_temp_dir(db_name: &str) -> (PathBuf, Box<dyn FnOnce() -> std::io::Result<()>>) {
-        let temp_dir = std::env::temp_dir().join(format!("{db_name}_dir"));
-        let full_path = temp_dir.clone().join(format!("{db_name}.db"));
-        // cleanup callback
-        let clean_up = Box::new(move || std::fs::remove_dir_all(temp_dir));
-        (full_path, clean_up)
-    }
+/// the number of urls waiting in the inbox
+pub fn count_inbox_items(conn: &Connection) -> DatabaseResult<u32> {
+    let query = "SELECT COUNT(*) FROM inbox";
+    Ok(conn.query_row(query, [], |row| row.get(0))?)
+}
 
-    #[test]
-    #[serial] // annotation that will make sure that this is run sequentially
-    fn create_db_once() {
-        let (db_path, cleanup) = create_temp_dir("create_db_once");
-        let conn = open_connection(&db_path);
-        assert!(conn.is_ok());
-        assert!(cleanup().is_ok());
-    }
+/// the number of review items, due or not
+pub fn count_items(conn: &Connection) -> DatabaseResult<u32> {
+    let query = "SELECT COUNT(*) FROM items";
+    Ok(conn.query_row(query, [], |row| row.get(0))?)
+}
 
-    #[test]
-    #[serial]
-    fn create_db_twice() {
-        let (db_path, cleanup) = create_temp_dir("create_db_once");
-        {
-            let conn = open_connection(&db_path);
-            assert!(conn.is_ok());
-        }
+/// gets a single item by its id
+pub fn get_item(conn: &Connection, id: ItemId) -> DatabaseResult<Item> {
+    let query = format!("SELECT {ITEM_COLUMNS} FROM items WHERE id=?");
+    let mut stmt = conn.prepare(&query)?;
+    let mut item = stmt.query_row([id], item_from_row)?;
+    item.tags = get_item_tags(conn, id)?;
+    Ok(item)
+}
 
-        let conn = open_connection(&db_path);
-        assert!(conn.is_ok());
-        // since we successfully created the db the second time we can also be sure that we do infact do a SCHEMA_VERSION number of schema altering changes
+/// writes the scheduling data and due date of every item back to its row. Either all items are
+/// updated or none of them are. Tags and notes are not touched, they have their own functions.
+pub fn update_items(conn: &mut Connection, items: Vec<Item>) -> DatabaseResult<()> {
+    let tx = conn.transaction()?;
+    write_items(&tx, items)?;
+    tx.commit()?;
+    Ok(())
+}
 
-        assert!(cleanup().is_ok());
+/// the statements behind [update_items], for callers that manage the transaction themselves
+fn write_items(conn: &Connection, items: Vec<Item>) -> DatabaseResult<()> {
+    let stmt_str = "UPDATE items SET \
+                interval=?, difficulty=?, memory_strength=?, adjusting_factor=?, \
+                times_reviewed=?, times_recalled=?, due=?, last_review=?, scheduler_state=? \
+            WHERE id=?";
+    let mut stmt = conn.prepare(stmt_str)?;
+    for item in items {
+        let data = &item.scheduling_data;
+        let n_changed = stmt.execute(params![
+            data.interval,
+            data.difficulty,
+            data.memory_strength,
+            data.adjusting_factor,
+            data.times_reviewed,
+            data.times_recalled,
+            item.due,
+            item.last_review,
+            scheduler_state_to_sql(&item.scheduler_state),
+            item.id,
+        ])?;
+        // updating an item that does not exist is an error, dropping the tx rolls back the others
+        if n_changed == 0 {
+            return Err(DatabaseErrorSource::SQLError(
+                rusqlite::Error::QueryReturnedNoRows,
+            ));
+        }
     }
+    Ok(())
+}
 
-    #[test]
-    #[serial]
-    fn add_and_get_one_new_items() {
-        let (db_path, cleanup) = create_temp_dir("add_and_get_one_new_items");
-        let url: String = "https://open.kattis.com/problems/hello".into();
-        let urls = vec![url.clone()];
-        // this connection should be fine according to other tests
-        let conn = open_connection(&db_path).unwrap();
-        let res_add = add_urls_to_inbox(&conn, urls);
-        // could we successfully add the item?
-        assert!(res_add.is_ok());
-        // we could!
-        // now can we retrieve that same item?
-        let res_get = get_n_urls_from_inbox(&conn, 1);
-        assert!(res_get.is_ok());
-        let res = res_get.unwrap();
-        assert!(res.len() == 1);
-        assert!(res[0].url == url);
-        assert!(cleanup().is_ok());
+// inserts a new item into the items table (when turning a new_item into an item)
+// Should return the id as provided by sqlite (we use this id when scheduling the item)
+// the id field of the provided item is ignored.
+pub fn insert_item(conn: &Connection, item: Item) -> DatabaseResult<ItemId> {
+    let stmt = "INSERT INTO items \
+                (interval, difficulty, memory_strength, adjusting_factor, times_reviewed, times_recalled, due, last_review, url, item_notes, scheduler_state) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+    let data = &item.scheduling_data;
+    conn.execute(
+        stmt,
+        params![
+            data.interval,
+            data.difficulty,
+            data.memory_strength,
+            data.adjusting_factor,
+            data.times_reviewed,
+            data.times_recalled,
+            item.due,
+            item.last_review,
+            item.url,
+            item.notes,
+            scheduler_state_to_sql(&item.scheduler_state),
+        ],
+    )?;
+    let item_id = conn.last_insert_rowid() as ItemId;
+    let tags: Vec<&str> = item.tags.iter().map(String::as_str).collect();
+    add_item_tags(conn, item_id, &tags)?;
+    if item.metadata.is_some() {
+        set_item_metadata(conn, item_id, item.metadata.as_ref())?;
+    }
+    if item.target.is_some() {
+        set_item_target(conn, item_id, item.target)?;
     }
+    Ok(item_id)
+}
 
-    #[test]
-    #[serial]
-    fn add_and_get_several_new_items() {
-        let (db_path, cleanup) = create_temp_dir("add_and_get_several_new_items");
-        let urls = vec![
-            "https://open.kattis.com/problems/hello".into(),
-            "https://open.kattis.com/problems/faktor".into(),
-            "https://open.kattis.com/problems/autori".into(),
-        ];
-        // this connection should be fine according to other tests
-        let conn = open_connection(&db_path).unwrap();
-        let res_add = add_urls_to_inbox(&conn, urls.clone());
-        // could we successfully add the items?
-        assert!(res_add.is_ok());
-        // we could!
-        // now can we retrieve that same items in queue order??
-        let res_get = get_n_urls_from_inbox(&conn, urls.len());
-        assert!(res_get.is_ok());
-        let res = res_get.unwrap();
-        let res_inner_strings: Vec<String> = res.into_iter().map(|item| item.url).collect();
-        assert!(res_inner_strings.len() == urls.len());
-        assert!(urls == res_inner_strings);
-        assert!(cleanup().is_ok());
+/// turns the inbox entry with id {inbox_id} into a review item with initial scheduling data that
+/// is due right away and carries the tags of the entry. Its scheduler state is the initial state
+/// of the algorithm {scheduling} picks for those tags. The entry is removed from the inbox in the
+/// same transaction, so we never end up with the url in both tables (or in neither).
+pub fn promote_inbox_item(
+    conn: &mut Connection,
+    inbox_id: u64,
+    scheduling: &SchedulingConfig,
+) -> DatabaseResult<ItemId> {
+    let tx = conn.transaction()?;
+    let item_id = promote(&tx, inbox_id, current_timestamp(), scheduling)?;
+    tx.commit()?;
+    Ok(item_id)
+}
+
+/// the statements behind [promote_inbox_item], the new item is due at {due}
+fn promote(
+    conn: &Connection,
+    inbox_id: u64,
+    due: u64,
+    scheduling: &SchedulingConfig,
+) -> DatabaseResult<ItemId> {
+    let url: String = conn.query_row("SELECT url FROM inbox WHERE id=?", [inbox_id], |row| {
+        row.get(0)
+    })?;
+    let tags = get_inbox_tags(conn, inbox_id)?;
+    remove_new_item(conn, inbox_id)?;
+    let scheduler = scheduling::scheduler(scheduling.algorithm_for(&tags));
+    let item = Item {
+        id: 0,
+        scheduling_data: SchedulingData::default(),
+        due,
+        last_review: None,
+        url,
+        tags,
+        notes: String::new(),
+        metadata: None,
+        scheduler_state: scheduler.initial_state(),
+        target: None,
+    };
+    insert_item(conn, item)
+}
+
+/// whether the daily drip already ran on {day}
+pub fn dripped_on(conn: &Connection, day: u64) -> DatabaseResult<bool> {
+    let query = "SELECT EXISTS(SELECT 1 FROM drips WHERE day=?)";
+    Ok(conn.query_row(query, [day], |row| row.get(0))?)
+}
+
+/// promotes the inbox entries {inbox_ids} as the drip of the day of {now} and logs them, all or
+/// nothing. Returns the new items, or None if the drip already ran that day.
+pub fn drip_inbox(
+    conn: &mut Connection,
+    inbox_ids: &[u64],
+    now: u64,
+    scheduling: &SchedulingConfig,
+) -> DatabaseResult<Option<Vec<ItemId>>> {
+    let day = day_of(now);
+    let tx = conn.transaction()?;
+    let stmt = "INSERT OR IGNORE INTO drips (day, timestamp) VALUES (?, ?)";
+    if tx.execute(stmt, [day, now])? == 0 {
+        return Ok(None);
     }
+    let mut item_ids = vec![];
+    for inbox_id in inbox_ids {
+        let item_id = promote(&tx, *inbox_id, now, scheduling)?;
+        let stmt = "INSERT INTO drip_promotions (day, item_id, url) \
+                SELECT ?, id, url FROM items WHERE id=?";
+        tx.execute(stmt, [day, item_id])?;
+        item_ids.push(item_id);
+    }
+    tx.commit()?;
+    Ok(Some(item_ids))
+}
 
-    #[test]
-    #[serial]
-    fn add_several_and_remove_some_new_items() {
-        let (db_path, cleanup) = create_temp_dir("add_several_and_remove_some_new_items");
-        // add some urls
-        let urls = vec![
-            "https://open.kattis.com/problems/hello".into(),
-            "https://open.kattis.com/problems/faktor".into(),
-            "https://open.kattis.com/problems/autori".into(),
-        ];
-        let conn = open_connection(&db_path).unwrap();
-        let _res_add = add_urls_to_inbox(&conn, urls.clone()).unwrap();
+/// whether we already spread the backlog of reviews on {day}
+pub fn backlog_spread_on(conn: &Connection, day: u64) -> DatabaseResult<bool> {
+    let query = "SELECT EXISTS(SELECT 1 FROM backlog_spreads WHERE day=?)";
+    Ok(conn.query_row(query, [day], |row| row.get(0))?)
+}
 
-        // now we remove some, readd them and see that the order has been changed
-        let res_get = get_n_urls_from_inbox(&conn, urls.len()).unwrap();
-        let res_inner_strings: Vec<String> = res_get.into_iter().map(|item| item.url).collect();
-        assert!(urls == res_inner_strings);
+/// remembers that we spread the backlog on the day of {now}
+pub fn mark_backlog_spread(conn: &Connection, now: u64) -> DatabaseResult<()> {
+    let stmt = "INSERT OR IGNORE INTO backlog_spreads (day, timestamp) VALUES (?, ?)";
+    conn.execute(stmt, [day_of(now), now])?;
+    Ok(())
+}
 
-        // can we remove items?
+/// every entry the daily drip promoted, oldest first
+pub fn get_drip_promotions(conn: &Connection) -> DatabaseResult<Vec<DripPromotion>> {
+    let query = "SELECT drips.timestamp, item_id, url FROM drip_promotions \
+            JOIN drips ON drips.day = drip_promotions.day ORDER BY drips.day, drip_promotions.rowid";
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(DripPromotion {
+                timestamp: row.get(0)?,
+                item_id: row.get(1)?,
+                url: row.get(2)?,
+            })
+        })?
+        .map(|res| res.expect("could not build DripPromotion from query!"))
+        .collect();
+    Ok(rows)
+}
+
+/// replaces the notes of an item and records the new version in its note history. Saving notes
+/// that did not change is a no-op.
+pub fn update_item_notes(
+    conn: &mut Connection,
+    item_id: ItemId,
+    notes: &str,
+    timestamp: u64,
+) -> DatabaseResult<()> {
+    let tx = conn.transaction()?;
+    let current: String = tx.query_row(
+        "SELECT item_notes FROM items WHERE id=?",
+        [item_id],
+        |row| row.get(0),
+    )?;
+    if current != notes {
+        tx.execute(
+            "UPDATE items SET item_notes=? WHERE id=?",
+            params![notes, item_id],
+        )?;
+        tx.execute(
+            "INSERT INTO note_history (item_id, notes, timestamp) VALUES (?, ?, ?)",
+            params![item_id, notes, timestamp],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// every saved version of an item's notes, oldest first
+pub fn get_note_history(conn: &Connection, item_id: ItemId) -> DatabaseResult<Vec<NoteRevision>> {
+    let query = "SELECT notes, timestamp FROM note_history WHERE item_id=? ORDER BY id";
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt
+        .query_map([item_id], |row| {
+            Ok(NoteRevision {
+                notes: row.get(0)?,
+                timestamp: row.get(1)?,
+            })
+        })?
+        .map(|res| res.expect("could not build NoteRevision from query!"))
+        .collect();
+    Ok(rows)
+}
+
+fn grade_to_sql(grade: &UserReview) -> u8 {
+    match grade {
+        UserReview::TooEasy => 1,
+        UserReview::JustEnough => 2,
+        UserReview::TooHard => 3,
+    }
+}
+
+fn grade_from_sql(grade: u8) -> rusqlite::Result<UserReview> {
+    match grade {
+        1 => Ok(UserReview::TooEasy),
+        2 => Ok(UserReview::JustEnough),
+        3 => Ok(UserReview::TooHard),
+        _ => Err(rusqlite::Error::IntegralValueOutOfRange(3, grade as i64)),
+    }
+}
+
+/// writes the reviewed item and the log entry describing the review in one transaction, so the
+/// history never disagrees with the scheduling data. Returns the id of the log entry.
+pub fn record_review(
+    conn: &mut Connection,
+    item: Item,
+    review: &ReviewLog,
+) -> DatabaseResult<ReviewId> {
+    let stmt = "INSERT INTO reviews \
+                (item_id, timestamp, grade, duration, interval_before, interval_after, difficulty_before, difficulty_after, verdict) \
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
+    let tx = conn.transaction()?;
+    write_items(&tx, vec![item])?;
+    tx.execute(
+        stmt,
+        params![
+            review.item_id,
+            review.timestamp,
+            grade_to_sql(&review.grade),
+            review.duration.map(|d| d.as_millis() as u64),
+            review.interval_before,
+            review.interval_after,
+            review.difficulty_before,
+            review.difficulty_after,
+            review.verdict.map(|verdict| verdict.code()),
+        ],
+    )?;
+    let review_id = tx.last_insert_rowid() as ReviewId;
+    tx.commit()?;
+    Ok(review_id)
+}
+
+/// takes back review {review_id} of {item}: the item gets the scheduling data and due date it had
+/// before the review and the review leaves the log. Solutions archived with the review are kept,
+/// they just no longer belong to a review.
+pub fn undo_review(conn: &mut Connection, item: Item, review_id: ReviewId) -> DatabaseResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        "UPDATE solutions SET review_id=NULL WHERE review_id=?",
+        [review_id],
+    )?;
+    let n_deleted = tx.execute(
+        "DELETE FROM reviews WHERE id=? AND item_id=?",
+        [review_id, item.id],
+    )?;
+    if n_deleted == 0 {
+        return Err(DatabaseErrorSource::SQLError(
+            rusqlite::Error::QueryReturnedNoRows,
+        ));
+    }
+    write_items(&tx, vec![item])?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn review_from_row(row: &Row<'_>) -> rusqlite::Result<ReviewLog> {
+    let duration: Option<u64> = row.get(4)?;
+    Ok(ReviewLog {
+        id: row.get(0)?,
+        item_id: row.get(1)?,
+        timestamp: row.get(2)?,
+        grade: grade_from_sql(row.get(3)?)?,
+        duration: duration.map(Duration::from_millis),
+        interval_before: row.get(5)?,
+        interval_after: row.get(6)?,
+        difficulty_before: row.get(7)?,
+        difficulty_after: row.get(8)?,
+        verdict: verdict_from_sql(row.get(9)?)?,
+    })
+}
+
+fn verdict_from_sql(code: Option<String>) -> rusqlite::Result<Option<Verdict>> {
+    code.map(|code| {
+        Verdict::from_code(&code)
+            .ok_or_else(|| rusqlite::Error::InvalidColumnType(9, code, rusqlite::types::Type::Text))
+    })
+    .transpose()
+}
+
+/// every review of an item, oldest first
+pub fn get_item_reviews(conn: &Connection, item_id: ItemId) -> DatabaseResult<Vec<ReviewLog>> {
+    let query = "SELECT id, item_id, timestamp, grade, duration, interval_before, interval_after, \
+                difficulty_before, difficulty_after, verdict \
+            FROM reviews WHERE item_id=? ORDER BY timestamp, id";
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt
+        .query_map([item_id], review_from_row)?
+        .map(|res| res.expect("could not build ReviewLog from query!"))
+        .collect();
+    Ok(rows)
+}
+
+/// adds a sample test to item {item_id}, returns its id
+pub fn add_sample_test(
+    conn: &Connection,
+    item_id: ItemId,
+    input: &str,
+    output: &str,
+) -> DatabaseResult<u64> {
+    let stmt = "INSERT INTO sample_tests (item_id, input, output) VALUES (?, ?, ?)";
+    conn.execute(stmt, params![item_id, input, output])?;
+    Ok(conn.last_insert_rowid() as u64)
+}
+
+/// replaces every sample test of item {item_id} with {samples}, pairs of input and output
+pub fn replace_sample_tests(
+    conn: &mut Connection,
+    item_id: ItemId,
+    samples: &[(String, String)],
+) -> DatabaseResult<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM sample_tests WHERE item_id=?", [item_id])?;
+    for (input, output) in samples {
+        add_sample_test(&tx, item_id, input, output)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// the sample tests of item {item_id}, in the order they were added
+pub fn get_sample_tests(conn: &Connection, item_id: ItemId) -> DatabaseResult<Vec<SampleTest>> {
+    let query = "SELECT input, output FROM sample_tests WHERE item_id=? ORDER BY id";
+    let mut stmt = conn.prepare(query)?;
+    let samples = stmt
+        .query_map([item_id], |row| {
+            Ok(SampleTest {
+                input: row.get(0)?,
+                output: row.get(1)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<SampleTest>>>()?;
+    Ok(samples)
+}
+
+/// archives {solution}, returns its id
+pub fn add_solution(conn: &Connection, solution: &Solution) -> DatabaseResult<u64> {
+    let stmt = "INSERT INTO solutions (item_id, review_id, language, source, timestamp) \
+            VALUES (?, ?, ?, ?, ?)";
+    conn.execute(
+        stmt,
+        params![
+            solution.item_id,
+            solution.review_id,
+            solution.language,
+            solution.source,
+            solution.timestamp
+        ],
+    )?;
+    Ok(conn.last_insert_rowid() as u64)
+}
+
+/// every solution of item {item_id}, oldest first
+pub fn get_item_solutions(conn: &Connection, item_id: ItemId) -> DatabaseResult<Vec<Solution>> {
+    let query = "SELECT id, item_id, review_id, language, source, timestamp \
+            FROM solutions WHERE item_id=? ORDER BY timestamp, id";
+    let mut stmt = conn.prepare(query)?;
+    let solutions = stmt
+        .query_map([item_id], |row| {
+            Ok(Solution {
+                id: row.get(0)?,
+                item_id: row.get(1)?,
+                review_id: row.get(2)?,
+                language: row.get(3)?,
+                source: row.get(4)?,
+                timestamp: row.get(5)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<Solution>>>()?;
+    Ok(solutions)
+}
+
+/// tags are case insensitive and surrounding whitespace is ignored. Returns None for blank tags.
+fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// gets the id of the tag called {name}, creating the tag if it does not exist yet
+fn get_or_create_tag(conn: &Connection, name: &str) -> DatabaseResult<i64> {
+    conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?)", [name])?;
+    Ok(conn.query_row("SELECT id FROM tags WHERE name=?", [name], |row| row.get(0))?)
+}
+
+// items and inbox entries share the tag logic, they only differ in which join table they use.
+// {table} and {column} are never user input.
+fn add_tags(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    id: u64,
+    tags: &[&str],
+) -> DatabaseResult<()> {
+    let stmt = format!("INSERT OR IGNORE INTO {table} ({column}, tag_id) VALUES (?, ?)");
+    for name in tags.iter().filter_map(|tag| normalize_tag(tag)) {
+        let tag_id = get_or_create_tag(conn, &name)?;
+        conn.execute(&stmt, params![id, tag_id])?;
+    }
+    Ok(())
+}
+
+fn remove_tag(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    id: u64,
+    tag: &str,
+) -> DatabaseResult<()> {
+    let stmt = format!(
+        "DELETE FROM {table} WHERE {column}=? AND tag_id IN (SELECT id FROM tags WHERE name=?)"
+    );
+    if let Some(name) = normalize_tag(tag) {
+        conn.execute(&stmt, params![id, name])?;
+    }
+    Ok(())
+}
+
+fn get_tags(conn: &Connection, table: &str, column: &str, id: u64) -> DatabaseResult<Vec<String>> {
+    let query = format!(
+        "SELECT tags.name FROM {table} JOIN tags ON tags.id = {table}.tag_id \
+            WHERE {table}.{column}=? ORDER BY tags.name"
+    );
+    let mut stmt = conn.prepare(&query)?;
+    let rows = stmt
+        .query_map([id], |row| row.get(0))?
+        .map(|res| res.expect("could not read tag name from query!"))
+        .collect();
+    Ok(rows)
+}
+
+/// tags the item with every tag in {tags}, tags it already has are ignored
+pub fn add_item_tags(conn: &Connection, item_id: ItemId, tags: &[&str]) -> DatabaseResult<()> {
+    add_tags(conn, "item_tags", "item_id", item_id, tags)
+}
+
+/// removes {tag} from the item, does nothing if the item was not tagged with it
+pub fn remove_item_tag(conn: &Connection, item_id: ItemId, tag: &str) -> DatabaseResult<()> {
+    remove_tag(conn, "item_tags", "item_id", item_id, tag)
+}
+
+/// the tags of an item in alphabetical order
+pub fn get_item_tags(conn: &Connection, item_id: ItemId) -> DatabaseResult<Vec<String>> {
+    get_tags(conn, "item_tags", "item_id", item_id)
+}
+
+/// tags the inbox entry with every tag in {tags}. They carry over to the item once it is promoted.
+pub fn add_inbox_tags(conn: &Connection, inbox_id: u64, tags: &[&str]) -> DatabaseResult<()> {
+    add_tags(conn, "inbox_tags", "inbox_id", inbox_id, tags)
+}
+
+/// removes {tag} from the inbox entry, does nothing if the entry was not tagged with it
+pub fn remove_inbox_tag(conn: &Connection, inbox_id: u64, tag: &str) -> DatabaseResult<()> {
+    remove_tag(conn, "inbox_tags", "inbox_id", inbox_id, tag)
+}
+
+/// the tags of an inbox entry in alphabetical order
+pub fn get_inbox_tags(conn: &Connection, inbox_id: u64) -> DatabaseResult<Vec<String>> {
+    get_tags(conn, "inbox_tags", "inbox_id", inbox_id)
+}
+
+/// every tag in use by at least one item or inbox entry, in alphabetical order
+pub fn list_tags(conn: &Connection) -> DatabaseResult<Vec<String>> {
+    let query = "SELECT name FROM tags \
+            WHERE id IN (SELECT tag_id FROM item_tags UNION SELECT tag_id FROM inbox_tags) \
+            ORDER BY name";
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt
+        .query_map([], |row| row.get(0))?
+        .map(|res| res.expect("could not read tag name from query!"))
+        .collect();
+    Ok(rows)
+}
+
+/// the day (counted since the unix epoch, in UTC) that {timestamp} falls on
+pub fn day_of(timestamp: u64) -> u64 {
+    timestamp / SECONDS_PER_DAY
+}
+
+/// number of reviews on each of the {n_days} days starting at day {from_day}
+pub fn reviews_per_day(
+    conn: &Connection,
+    from_day: u64,
+    n_days: usize,
+) -> DatabaseResult<Vec<u32>> {
+    let query = "SELECT timestamp / ? AS day, COUNT(*) FROM reviews \
+            WHERE timestamp >= ? AND timestamp < ? GROUP BY day";
+    let start = from_day * SECONDS_PER_DAY;
+    let end = (from_day + n_days as u64) * SECONDS_PER_DAY;
+    let mut counts = vec![0; n_days];
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map(params![SECONDS_PER_DAY, start, end], |row| {
+        Ok((row.get::<_, u64>(0)?, row.get::<_, u32>(1)?))
+    })?;
+    for row in rows {
+        let (day, count) = row?;
+        counts[(day - from_day) as usize] = count;
+    }
+    Ok(counts)
+}
+
+/// number of items due on each of the {n_days} days starting with the day of {now}. Items that are
+/// already overdue are counted towards the first day, buried items towards the day they come back.
+pub fn due_forecast(conn: &Connection, now: u64, n_days: usize) -> DatabaseResult<Vec<u32>> {
+    let query = "SELECT MAX(MAX(due, IFNULL(buried_until, 0)) / ? - ?, 0) AS day, COUNT(*) \
+            FROM items WHERE status IN ('active', 'buried') \
+            AND MAX(due, IFNULL(buried_until, 0)) < ? GROUP BY day";
+    let today = day_of(now);
+    let end = (today + n_days as u64) * SECONDS_PER_DAY;
+    let mut counts = vec![0; n_days];
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map(params![SECONDS_PER_DAY, today, end], |row| {
+        Ok((row.get::<_, usize>(0)?, row.get::<_, u32>(1)?))
+    })?;
+    for row in rows {
+        let (day, count) = row?;
+        counts[day] = count;
+    }
+    Ok(counts)
+}
+
+/// the share of logged reviews that were not graded hard, None if nothing has been reviewed. We go
+/// by the grades rather than times_recalled, which spaced-rs also counts up for hard reviews, so
+/// the rate means the same whatever scheduled the items.
+pub fn retention_rate(conn: &Connection) -> DatabaseResult<Option<f64>> {
+    let query = "SELECT AVG(grade <> ?) FROM reviews";
+    let forgotten = grade_to_sql(&UserReview::TooHard);
+    Ok(conn.query_row(query, [forgotten], |row| row.get(0))?)
+}
+
+/// the retention rate of the reviews of the items carrying each tag, for every tag with reviews
+pub fn retention_rate_per_tag(conn: &Connection) -> DatabaseResult<Vec<(String, f64)>> {
+    let query = "SELECT tags.name, AVG(reviews.grade <> ?) FROM reviews \
+            JOIN item_tags ON item_tags.item_id = reviews.item_id \
+            JOIN tags ON tags.id = item_tags.tag_id \
+            GROUP BY tags.name ORDER BY tags.name";
+    let forgotten = grade_to_sql(&UserReview::TooHard);
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt
+        .query_map([forgotten], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .map(|res| res.expect("could not compute retention rate from query!"))
+        .collect();
+    Ok(rows)
+}
+
+/// the average duration of the reviews that were timed
+pub fn average_solve_time(conn: &Connection) -> DatabaseResult<Option<Duration>> {
+    let query = "SELECT AVG(duration) FROM reviews WHERE duration IS NOT NULL";
+    let millis: Option<f64> = conn.query_row(query, [], |row| row.get(0))?;
+    Ok(millis.map(|millis| Duration::from_millis(millis.round() as u64)))
+}
+
+/// the average time spent on each item, over its timed reviews
+pub fn average_solve_times(conn: &Connection) -> DatabaseResult<HashMap<ItemId, Duration>> {
+    let query = "SELECT item_id, AVG(duration) FROM reviews WHERE duration IS NOT NULL \
+            GROUP BY item_id";
+    let mut stmt = conn.prepare(query)?;
+    let rows = stmt.query_map([], |row| {
+        let millis: f64 = row.get(1)?;
+        Ok((row.get(0)?, Duration::from_millis(millis.round() as u64)))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// what we reviewed on the day of {now}. A review is the first review of an item when the log has
+/// no earlier one, so the counts survive restarts and undone reviews no longer count.
+pub fn done_today(conn: &Connection, now: u64) -> DatabaseResult<DoneToday> {
+    let query = "SELECT COUNT(*), \
+                IFNULL(SUM(NOT EXISTS (SELECT 1 FROM reviews earlier \
+                    WHERE earlier.item_id = reviews.item_id AND earlier.id < reviews.id)), 0), \
+                IFNULL(SUM(duration), 0) \
+            FROM reviews WHERE timestamp >= ? AND timestamp < ?";
+    let start = day_of(now) * SECONDS_PER_DAY;
+    let (total, new, millis): (u32, u32, u64) =
+        conn.query_row(query, [start, start + SECONDS_PER_DAY], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+    Ok(DoneToday {
+        reviews: total - new,
+        new,
+        time: Duration::from_millis(millis),
+    })
+}
+
+/// everything the stats screen shows, as of {now}
+pub fn get_stats(conn: &Connection, now: u64) -> DatabaseResult<Stats> {
+    let today = day_of(now);
+    // the heatmap has a column per week, the first one starting on a monday (day 0 was a thursday)
+    let this_monday = today - (today + 3) % 7;
+    let heatmap_start_day = this_monday - (HEATMAP_WEEKS - 1) * 7;
+    let n_days = (today - heatmap_start_day + 1) as usize;
+    Ok(Stats {
+        heatmap_start_day,
+        reviews_per_day: reviews_per_day(conn, heatmap_start_day, n_days)?,
+        retention: retention_rate(conn)?,
+        retention_per_tag: retention_rate_per_tag(conn)?,
+        due_forecast: due_forecast(conn, now, FORECAST_DAYS)?,
+        average_solve_time: average_solve_time(conn)?,
+    })
+}
+
+/// seconds since the unix epoch, the unit used by the due column
+pub fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is set before the unix epoch!")
+        .as_secs()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::scheduling::Algorithm;
+    use serial_test::serial;
+    use std::path::PathBuf;
+
+    /// util function to create temp path for db_instance. Returns path and cleanup function.
+    pub(crate) fn create_temp_dir(
+        db_name: &str,
+    ) -> (PathBuf, Box<dyn FnOnce() -> std::io::Result<()>>) {
+        let temp_dir = std::env::temp_dir().join(format!("{db_name}_dir"));
+        let full_path = temp_dir.clone().join(format!("{db_name}.db"));
+        // cleanup callback
+        let clean_up = Box::new(move || std::fs::remove_dir_all(temp_dir));
+        (full_path, clean_up)
+    }
+
+    #[test]
+    #[serial] // annotation that will make sure that this is run sequentially
+    fn create_db_once() {
+        let (db_path, cleanup) = create_temp_dir("create_db_once");
+        let conn = open_connection(&db_path);
+        assert!(conn.is_ok());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn create_db_twice() {
+        let (db_path, cleanup) = create_temp_dir("create_db_once");
+        {
+            let conn = open_connection(&db_path);
+            assert!(conn.is_ok());
+        }
+
+        let conn = open_connection(&db_path);
+        assert!(conn.is_ok());
+        // since we successfully created the db the second time we can also be sure that we do infact do a SCHEMA_VERSION number of schema altering changes
+
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn add_and_get_one_new_items() {
+        let (db_path, cleanup) = create_temp_dir("add_and_get_one_new_items");
+        let url: String = "https://open.kattis.com/problems/hello".into();
+        let urls = vec![url.clone()];
+        // this connection should be fine according to other tests
+        let conn = open_connection(&db_path).unwrap();
+        let res_add = add_urls_to_inbox(&conn, urls);
+        // could we successfully add the item?
+        assert!(res_add.is_ok());
+        // we could!
+        // now can we retrieve that same item?
+        let res_get = get_n_urls_from_inbox(&conn, 1);
+        assert!(res_get.is_ok());
+        let res = res_get.unwrap();
+        assert!(res.len() == 1);
+        assert!(res[0].url == url);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn add_and_get_several_new_items() {
+        let (db_path, cleanup) = create_temp_dir("add_and_get_several_new_items");
+        let urls = vec![
+            "https://open.kattis.com/problems/hello".into(),
+            "https://open.kattis.com/problems/faktor".into(),
+            "https://open.kattis.com/problems/autori".into(),
+        ];
+        // this connection should be fine according to other tests
+        let conn = open_connection(&db_path).unwrap();
+        let res_add = add_urls_to_inbox(&conn, urls.clone());
+        // could we successfully add the items?
+        assert!(res_add.is_ok());
+        // we could!
+        // now can we retrieve that same items in queue order??
+        let res_get = get_n_urls_from_inbox(&conn, urls.len());
+        assert!(res_get.is_ok());
+        let res = res_get.unwrap();
+        let res_inner_strings: Vec<String> = res.into_iter().map(|item| item.url).collect();
+        assert!(res_inner_strings.len() == urls.len());
+        assert!(urls == res_inner_strings);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn add_several_and_remove_some_new_items() {
+        let (db_path, cleanup) = create_temp_dir("add_several_and_remove_some_new_items");
+        // add some urls
+        let urls = vec![
+            "https://open.kattis.com/problems/hello".into(),
+            "https://open.kattis.com/problems/faktor".into(),
+            "https://open.kattis.com/problems/autori".into(),
+        ];
+        let conn = open_connection(&db_path).unwrap();
+        add_urls_to_inbox(&conn, urls.clone()).unwrap();
+
+        // now we remove some, readd them and see that the readded ones end up at the bottom
+        let res_get = get_n_urls_from_inbox(&conn, urls.len()).unwrap();
+        let res_inner_strings: Vec<String> = res_get.into_iter().map(|item| item.url).collect();
+        assert!(urls == res_inner_strings);
+
+        // can we remove items?
         assert!(remove_new_item(&conn, 1).is_ok());
         // yes we can!
         // can we insert them again and get the items in the expected order?
@@ -283,4 +1542,1019 @@ mod tests {
 
         assert!(cleanup().is_ok());
     }
+
+    #[test]
+    #[serial]
+    fn add_urls_reports_duplicates() {
+        let (db_path, cleanup) = create_temp_dir("add_urls_reports_duplicates");
+        let conn = open_connection(&db_path).unwrap();
+        insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        let urls = vec![
+            "http://kattis.com/problems/faktor/".into(),
+            "https://open.kattis.com/problems/faktor?utm_source=rss".into(),
+            "https://kattis.com/problems/hello".into(),
+            "https://open.kattis.com/problems/autori".into(),
+        ];
+        let report = add_urls_to_inbox(&conn, urls).unwrap();
+        assert!(
+            report.added
+                == vec![
+                    "https://open.kattis.com/problems/faktor",
+                    "https://open.kattis.com/problems/autori"
+                ]
+        );
+        assert!(report.already_in_inbox == vec!["https://open.kattis.com/problems/faktor"]);
+        assert!(report.already_items == vec!["https://open.kattis.com/problems/hello"]);
+        assert!(count_inbox_items(&conn).unwrap() == 2);
+        // the inbox itself refuses duplicates
+        let res = conn.execute(
+            "INSERT INTO inbox (url) VALUES (?)",
+            ["https://open.kattis.com/problems/autori"],
+        );
+        assert!(res.is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn upgrade_removes_duplicate_inbox_urls() {
+        let (db_path, cleanup) = create_temp_dir("upgrade_removes_duplicate_inbox_urls");
+        create_db_from_fixture(&db_path, FIXTURES[5].1);
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            let sql = "INSERT INTO inbox (url) VALUES \
+                ('https://open.kattis.com/problems/faktor'), \
+                ('https://open.kattis.com/problems/autori'), \
+                ('https://open.kattis.com/problems/sequences')";
+            conn.execute(sql, []).unwrap();
+        }
+        let conn = open_connection(&db_path).unwrap();
+        // faktor is an item already and autori was in the inbox first
+        let inbox = get_n_urls_from_inbox(&conn, 10).unwrap();
+        let urls: Vec<&str> = inbox.iter().map(|url_item| url_item.url.as_str()).collect();
+        assert!(
+            urls == vec![
+                "https://open.kattis.com/problems/autori",
+                "https://open.kattis.com/problems/sequences"
+            ]
+        );
+        assert!(inbox[0].id == 1);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn move_inbox_items() {
+        let (db_path, cleanup) = create_temp_dir("move_inbox_items");
+        let mut conn = open_connection(&db_path).unwrap();
+        let urls = vec![
+            "https://open.kattis.com/problems/hello".into(),
+            "https://open.kattis.com/problems/faktor".into(),
+            "https://open.kattis.com/problems/autori".into(),
+        ];
+        add_urls_to_inbox(&conn, urls).unwrap();
+        let inbox_ids = |conn: &Connection| -> Vec<u64> {
+            let inbox = get_n_urls_from_inbox(conn, 10).unwrap();
+            inbox.iter().map(|url_item| url_item.id).collect()
+        };
+
+        assert!(move_inbox_item(&mut conn, 3, 0).is_ok());
+        assert!(inbox_ids(&conn) == vec![3, 1, 2]);
+        assert!(move_inbox_item(&mut conn, 3, 1).is_ok());
+        assert!(inbox_ids(&conn) == vec![1, 3, 2]);
+        // past the end is the bottom
+        assert!(move_inbox_item(&mut conn, 1, 42).is_ok());
+        assert!(inbox_ids(&conn) == vec![3, 2, 1]);
+        // new urls are added below the moved ones
+        add_urls_to_inbox(
+            &conn,
+            vec!["https://open.kattis.com/problems/carrots".into()],
+        )
+        .unwrap();
+        assert!(inbox_ids(&conn) == vec![3, 2, 1, 4]);
+        assert!(move_inbox_item(&mut conn, 42, 0).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn inbox_order_follows_positions() {
+        let (db_path, cleanup) = create_temp_dir("inbox_order_follows_positions");
+        let mut conn = open_connection(&db_path).unwrap();
+        let urls = vec![
+            "https://open.kattis.com/problems/hello".into(),
+            "https://open.kattis.com/problems/faktor".into(),
+            "https://open.kattis.com/problems/autori".into(),
+        ];
+        add_urls_to_inbox(&conn, urls).unwrap();
+        let inbox_urls = |conn: &Connection, n: usize| -> Vec<String> {
+            let inbox = get_n_urls_from_inbox(conn, n).unwrap();
+            inbox.into_iter().map(|url_item| url_item.url).collect()
+        };
+
+        assert!(move_inbox_item_to_top(&mut conn, 3).is_ok());
+        assert!(move_inbox_item_to_bottom(&mut conn, 1).is_ok());
+        assert!(
+            inbox_urls(&conn, 3)
+                == vec![
+                    "https://open.kattis.com/problems/autori",
+                    "https://open.kattis.com/problems/faktor",
+                    "https://open.kattis.com/problems/hello",
+                ]
+        );
+        // the top of the queue is what gets promoted next, whatever its id
+        assert!(inbox_urls(&conn, 1) == vec!["https://open.kattis.com/problems/autori"]);
+        // a re-added url goes to the bottom even though a lower id became free
+        remove_new_item(&conn, 2).unwrap();
+        add_urls_to_inbox(
+            &conn,
+            vec!["https://open.kattis.com/problems/faktor".into()],
+        )
+        .unwrap();
+        assert!(
+            inbox_urls(&conn, 3)
+                == vec![
+                    "https://open.kattis.com/problems/autori",
+                    "https://open.kattis.com/problems/hello",
+                    "https://open.kattis.com/problems/faktor",
+                ]
+        );
+        assert!(move_inbox_item_to_top(&mut conn, 42).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    /// sql dumps of every schema we have released, paired with the user_version they carry
+    const FIXTURES: &[(SchemaVersion, &str)] = &[
+        (0, include_str!("../tests/fixtures/schema_v0.sql")),
+        (1, include_str!("../tests/fixtures/schema_v1.sql")),
+        (2, include_str!("../tests/fixtures/schema_v2.sql")),
+        (3, include_str!("../tests/fixtures/schema_v3.sql")),
+        (4, include_str!("../tests/fixtures/schema_v4.sql")),
+        (5, include_str!("../tests/fixtures/schema_v5.sql")),
+        (6, include_str!("../tests/fixtures/schema_v6.sql")),
+        (7, include_str!("../tests/fixtures/schema_v7.sql")),
+        (8, include_str!("../tests/fixtures/schema_v8.sql")),
+        (9, include_str!("../tests/fixtures/schema_v9.sql")),
+        (10, include_str!("../tests/fixtures/schema_v10.sql")),
+        (11, include_str!("../tests/fixtures/schema_v11.sql")),
+        (12, include_str!("../tests/fixtures/schema_v12.sql")),
+        (13, include_str!("../tests/fixtures/schema_v13.sql")),
+        (14, include_str!("../tests/fixtures/schema_v14.sql")),
+        (15, include_str!("../tests/fixtures/schema_v15.sql")),
+    ];
+
+    /// util function that creates a database at {path} from one of our fixtures
+    fn create_db_from_fixture(path: &Path, fixture: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(fixture).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn fresh_db_has_latest_schema() {
+        let (db_path, cleanup) = create_temp_dir("fresh_db_has_latest_schema");
+        let conn = open_connection(&db_path).unwrap();
+        assert!(schema_version(&conn).unwrap() == latest_schema_version());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn upgrade_historical_schemas() {
+        // every fixture must be the schema that some migration starts from
+        assert!(FIXTURES.len() == latest_schema_version() + 1);
+        for (version, fixture) in FIXTURES {
+            let (db_path, cleanup) = create_temp_dir(&format!("upgrade_schema_v{version}"));
+            create_db_from_fixture(&db_path, fixture);
+            {
+                let conn = open_connection(&db_path);
+                assert!(conn.is_ok());
+                let conn = conn.unwrap();
+                assert!(schema_version(&conn).unwrap() == latest_schema_version());
+                // the review history survived the upgrade
+                let item = get_item(&conn, 2).unwrap();
+                assert!(item.url == "https://open.kattis.com/problems/faktor");
+                assert!(item.scheduling_data.times_reviewed == 1);
+                assert!(item.due == 1654345600);
+                // the last review is taken from the log, or worked out from the due date
+                assert!(item.last_review == Some(1654000000));
+                assert!(get_item(&conn, 1).unwrap().last_review.is_none());
+                let inbox = get_n_urls_from_inbox(&conn, 10).unwrap();
+                assert!(inbox.len() == 1);
+                assert!(inbox[0].url == "https://open.kattis.com/problems/autori");
+            }
+            // we only take a backup when we actually migrated something
+            let backup =
+                db_path.with_file_name(format!("upgrade_schema_v{version}.db.v{version}.bak"));
+            assert!(backup.exists() == (*version < latest_schema_version()));
+            assert!(cleanup().is_ok());
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn newer_schema_is_rejected() {
+        let (db_path, cleanup) = create_temp_dir("newer_schema_is_rejected");
+        {
+            let conn = open_connection(&db_path).unwrap();
+            conn.pragma_update(None, "user_version", latest_schema_version() + 1)
+                .unwrap();
+        }
+        let res = open_connection(&db_path);
+        assert!(
+            matches!(res, Err(DatabaseErrorSource::InvalidSchemaError(v)) if v == latest_schema_version() + 1)
+        );
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn failing_migration_rolls_back() {
+        let (db_path, cleanup) = create_temp_dir("failing_migration_rolls_back");
+        std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+        let mut conn = Connection::open(&db_path).unwrap();
+        fn broken(conn: &Connection) -> rusqlite::Result<()> {
+            conn.execute("ALTER TABLE no_such_table ADD COLUMN oops TEXT", [])?;
+            Ok(())
+        }
+        let res = run_migrations(&mut conn, &[init_schema, broken]);
+        assert!(matches!(
+            res,
+            Err(DatabaseErrorSource::MigrationError { from: 1, to: 2, .. })
+        ));
+        // the first step was rolled back together with the broken one
+        assert!(schema_version(&conn).unwrap() == 0);
+        assert!(get_n_urls_from_inbox(&conn, 1).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    /// util function that builds an item with initial scheduling data, tests that need more
+    /// override the fields they care about
+    pub(crate) fn new_item(url: &str, due: u64) -> Item {
+        Item {
+            id: 0,
+            scheduling_data: SchedulingData::default(),
+            due,
+            last_review: None,
+            url: url.into(),
+            tags: vec![],
+            notes: String::new(),
+            metadata: None,
+            scheduler_state: SchedulerState::default(),
+            target: None,
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn insert_items_returns_sqlite_ids() {
+        let (db_path, cleanup) = create_temp_dir("insert_items_returns_sqlite_ids");
+        let conn = open_connection(&db_path).unwrap();
+        let first = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        );
+        let second = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/faktor", 20),
+        );
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert!(first != second);
+        // the ids we got back should point at the rows we inserted
+        let item = get_item(&conn, second).unwrap();
+        assert!(item.id == second);
+        assert!(item.url == "https://open.kattis.com/problems/faktor");
+        assert!(item.due == 20);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn insert_same_url_twice_fails() {
+        let (db_path, cleanup) = create_temp_dir("insert_same_url_twice_fails");
+        let conn = open_connection(&db_path).unwrap();
+        let url = "https://open.kattis.com/problems/hello";
+        assert!(insert_item(&conn, new_item(url, 10)).is_ok());
+        assert!(insert_item(&conn, new_item(url, 10)).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn update_several_items() {
+        let (db_path, cleanup) = create_temp_dir("update_several_items");
+        let mut conn = open_connection(&db_path).unwrap();
+        let hello = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        let faktor = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/faktor", 10),
+        )
+        .unwrap();
+
+        let updated: Vec<Item> = [hello, faktor]
+            .into_iter()
+            .map(|id| Item {
+                id,
+                scheduling_data: SchedulingData {
+                    interval: 4,
+                    difficulty: 11.0,
+                    memory_strength: 160.0,
+                    adjusting_factor: 0.9,
+                    times_reviewed: 1,
+                    times_recalled: 1,
+                },
+                ..new_item("", 1000 + id)
+            })
+            .collect();
+        assert!(update_items(&mut conn, updated).is_ok());
+
+        for id in [hello, faktor] {
+            let item = get_item(&conn, id).unwrap();
+            let data = item.scheduling_data;
+            assert!(data.interval == 4);
+            assert!(data.difficulty == 11.0);
+            assert!(data.memory_strength == 160.0);
+            assert!(data.adjusting_factor == 0.9);
+            assert!(data.times_reviewed == 1);
+            assert!(data.times_recalled == 1);
+            assert!(item.due == 1000 + id);
+        }
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn update_items_is_all_or_nothing() {
+        let (db_path, cleanup) = create_temp_dir("update_items_is_all_or_nothing");
+        let mut conn = open_connection(&db_path).unwrap();
+        let hello = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+
+        // the second item does not exist, so the first update should be rolled back
+        let mut existing = new_item("", 500);
+        existing.id = hello;
+        let mut missing = new_item("", 500);
+        missing.id = hello + 100;
+        assert!(update_items(&mut conn, vec![existing, missing]).is_err());
+        assert!(get_item(&conn, hello).unwrap().due == 10);
+
+        // a single update goes through
+        let mut existing = new_item("", 500);
+        existing.id = hello;
+        assert!(update_items(&mut conn, vec![existing]).is_ok());
+        assert!(get_item(&conn, hello).unwrap().due == 500);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn promote_inbox_item_moves_url() {
+        let (db_path, cleanup) = create_temp_dir("promote_inbox_item_moves_url");
+        let mut conn = open_connection(&db_path).unwrap();
+        let urls = vec![
+            "https://open.kattis.com/problems/hello".into(),
+            "https://open.kattis.com/problems/faktor".into(),
+        ];
+        add_urls_to_inbox(&conn, urls).unwrap();
+
+        let before = current_timestamp();
+        let item_id = promote_inbox_item(&mut conn, 1, &SchedulingConfig::default());
+        assert!(item_id.is_ok());
+        let item = get_item(&conn, item_id.unwrap()).unwrap();
+        assert!(item.url == "https://open.kattis.com/problems/hello");
+        // fresh items start with the default scheduling data and are due right away
+        assert!(item.scheduling_data.times_reviewed == 0);
+        assert!(item.due >= before);
+
+        // the entry is gone from the inbox
+        let inbox = get_n_urls_from_inbox(&conn, 10).unwrap();
+        assert!(inbox.len() == 1);
+        assert!(inbox[0].url == "https://open.kattis.com/problems/faktor");
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn failed_promote_keeps_inbox_entry() {
+        let (db_path, cleanup) = create_temp_dir("failed_promote_keeps_inbox_entry");
+        let mut conn = open_connection(&db_path).unwrap();
+        let url = "https://open.kattis.com/problems/hello";
+        add_urls_to_inbox(&conn, vec![url.into()]).unwrap();
+        // an item with the same url already exists, so the insert violates the UNIQUE constraint
+        insert_item(&conn, new_item(url, 10)).unwrap();
+
+        assert!(promote_inbox_item(&mut conn, 1, &SchedulingConfig::default()).is_err());
+        // the delete was rolled back together with the failed insert
+        assert!(get_n_urls_from_inbox(&conn, 10).unwrap().len() == 1);
+        // promoting something that is not in the inbox fails as well
+        assert!(promote_inbox_item(&mut conn, 42, &SchedulingConfig::default()).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn drip_runs_once_a_day() {
+        let (db_path, cleanup) = create_temp_dir("drip_runs_once_a_day");
+        let mut conn = open_connection(&db_path).unwrap();
+        let urls = ["hello", "faktor", "autori"]
+            .iter()
+            .map(|problem| format!("https://open.kattis.com/problems/{problem}"))
+            .collect();
+        add_urls_to_inbox(&conn, urls).unwrap();
+        let now = 19000 * SECONDS_PER_DAY + 100;
+        let scheduling = SchedulingConfig::default();
+        assert!(!dripped_on(&conn, 19000).unwrap());
+
+        let promoted = drip_inbox(&mut conn, &[3, 1], now, &scheduling)
+            .unwrap()
+            .unwrap();
+        assert!(dripped_on(&conn, 19000).unwrap());
+        assert!(get_item(&conn, promoted[0])
+            .unwrap()
+            .url
+            .ends_with("autori"));
+        assert!(get_item(&conn, promoted[1]).unwrap().due == now);
+        assert!(count_inbox_items(&conn).unwrap() == 1);
+        // a second run on the same day does nothing, even later that day
+        assert!(drip_inbox(&mut conn, &[2], now + 3600, &scheduling)
+            .unwrap()
+            .is_none());
+        assert!(count_inbox_items(&conn).unwrap() == 1);
+        // an empty drip still counts as the drip of the day
+        assert!(
+            drip_inbox(&mut conn, &[], now + SECONDS_PER_DAY, &scheduling).unwrap() == Some(vec![])
+        );
+        assert!(dripped_on(&conn, 19001).unwrap());
+
+        let log = get_drip_promotions(&conn).unwrap();
+        let urls: Vec<&str> = log.iter().map(|promotion| promotion.url.as_str()).collect();
+        assert!(
+            urls == vec![
+                "https://open.kattis.com/problems/autori",
+                "https://open.kattis.com/problems/hello"
+            ]
+        );
+        assert!(log[0].timestamp == now && log[0].item_id == Some(promoted[0]));
+        // a failing promotion leaves no trace of the drip
+        assert!(drip_inbox(&mut conn, &[42], now + 2 * SECONDS_PER_DAY, &scheduling).is_err());
+        assert!(!dripped_on(&conn, 19002).unwrap());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn add_list_and_remove_tags() {
+        let (db_path, cleanup) = create_temp_dir("add_list_and_remove_tags");
+        let conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        // tags are normalized and duplicates are ignored
+        assert!(add_item_tags(&conn, id, &["Graphs", " dp ", "graphs", ""]).is_ok());
+        assert!(get_item_tags(&conn, id).unwrap() == vec!["dp", "graphs"]);
+        assert!(get_item(&conn, id).unwrap().tags == vec!["dp", "graphs"]);
+
+        assert!(remove_item_tag(&conn, id, "DP").is_ok());
+        assert!(get_item_tags(&conn, id).unwrap() == vec!["graphs"]);
+        // removing a tag the item does not have is fine
+        assert!(remove_item_tag(&conn, id, "strings").is_ok());
+        // dp is no longer used by anything
+        assert!(list_tags(&conn).unwrap() == vec!["graphs"]);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn inbox_tags_follow_promotion() {
+        let (db_path, cleanup) = create_temp_dir("inbox_tags_follow_promotion");
+        let mut conn = open_connection(&db_path).unwrap();
+        add_urls_to_inbox(&conn, vec!["https://open.kattis.com/problems/hello".into()]).unwrap();
+        assert!(add_inbox_tags(&conn, 1, &["strings", "easy"]).is_ok());
+        assert!(remove_inbox_tag(&conn, 1, "easy").is_ok());
+        let inbox = get_n_urls_from_inbox(&conn, 1).unwrap();
+        assert!(inbox[0].tags == vec!["strings"]);
+
+        // the item starts out in the state of the algorithm for its tags
+        let scheduling = SchedulingConfig {
+            tags: [("strings".to_string(), Algorithm::Leitner)].into(),
+            ..SchedulingConfig::default()
+        };
+        let item_id = promote_inbox_item(&mut conn, 1, &scheduling).unwrap();
+        assert!(get_item_tags(&conn, item_id).unwrap() == vec!["strings"]);
+        let state = get_item(&conn, item_id).unwrap().scheduler_state;
+        assert!(state == SchedulerState::Leitner { box_index: 0 });
+        // the inbox tags went away together with the entry
+        assert!(get_inbox_tags(&conn, 1).unwrap().is_empty());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn due_items_filtered_by_tags() {
+        let (db_path, cleanup) = create_temp_dir("due_items_filtered_by_tags");
+        let conn = open_connection(&db_path).unwrap();
+        let mut graphs = new_item("https://open.kattis.com/problems/graphs", 10);
+        graphs.tags = vec!["graphs".into()];
+        let mut both = new_item("https://open.kattis.com/problems/both", 10);
+        both.tags = vec!["graphs".into(), "dp".into()];
+        let mut later = new_item("https://open.kattis.com/problems/later", 1000);
+        later.tags = vec!["graphs".into(), "dp".into()];
+        let untagged = new_item("https://open.kattis.com/problems/untagged", 10);
+        for item in [graphs, both, later, untagged] {
+            insert_item(&conn, item).unwrap();
+        }
+
+        let urls = |items: Vec<Item>| -> Vec<String> {
+            let mut urls: Vec<String> = items.into_iter().map(|item| item.url).collect();
+            urls.sort();
+            urls
+        };
+        let any = get_due_items_with_tags(&conn, 100, &["graphs", "dp"], false).unwrap();
+        assert!(
+            urls(any)
+                == vec![
+                    "https://open.kattis.com/problems/both",
+                    "https://open.kattis.com/problems/graphs"
+                ]
+        );
+        let all = get_due_items_with_tags(&conn, 100, &["graphs", "DP"], true).unwrap();
+        assert!(urls(all) == vec!["https://open.kattis.com/problems/both"]);
+        // the due date still applies
+        let all = get_due_items_with_tags(&conn, 1000, &["dp"], true).unwrap();
+        assert!(all.len() == 2);
+        // no tags means no restriction
+        let unrestricted = get_due_items_with_tags(&conn, 100, &[], true).unwrap();
+        assert!(unrestricted.len() == 3);
+        assert!(get_due_items(&conn, 100).unwrap().len() == 3);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn item_metadata_round_trip() {
+        let (db_path, cleanup) = create_temp_dir("item_metadata_round_trip");
+        let conn = open_connection(&db_path).unwrap();
+        let mut item = new_item("https://codeforces.com/problemset/problem/4/A", 10);
+        let mut metadata = ProblemMetadata::new("codeforces", "4A", Some("4".into()), "4A");
+        metadata.difficulty = Some("800".into());
+        item.metadata = Some(metadata.clone());
+        let id = insert_item(&conn, item).unwrap();
+        assert!(get_item(&conn, id).unwrap().metadata.as_ref() == Some(&metadata));
+
+        metadata.title = Some("A. Watermelon".into());
+        assert!(set_item_metadata(&conn, id, Some(&metadata)).is_ok());
+        assert!(get_item(&conn, id).unwrap().metadata == Some(metadata));
+        assert!(set_item_metadata(&conn, id, None).is_ok());
+        assert!(get_item(&conn, id).unwrap().metadata.is_none());
+        assert!(set_item_metadata(&conn, 42, None).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn item_target_round_trip() {
+        let (db_path, cleanup) = create_temp_dir("item_target_round_trip");
+        let conn = open_connection(&db_path).unwrap();
+        let mut item = new_item("https://open.kattis.com/problems/hello", 10);
+        item.target = Some(Duration::from_secs(20 * 60));
+        let id = insert_item(&conn, item).unwrap();
+        assert!(get_item(&conn, id).unwrap().target == Some(Duration::from_secs(1200)));
+        assert!(set_item_target(&conn, id, None).is_ok());
+        assert!(get_item(&conn, id).unwrap().target.is_none());
+        assert!(set_item_target(&conn, 42, None).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn scheduler_state_round_trip() {
+        let (db_path, cleanup) = create_temp_dir("scheduler_state_round_trip");
+        let mut conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        assert!(get_item(&conn, id).unwrap().scheduler_state == SchedulerState::SpacedRs);
+
+        for state in [
+            SchedulerState::Sm2 {
+                repetitions: 3,
+                ease: 2.36,
+            },
+            SchedulerState::Leitner { box_index: 4 },
+            SchedulerState::Fsrs {
+                stability: 12.5,
+                difficulty: 4.75,
+            },
+            SchedulerState::SpacedRs,
+        ] {
+            let mut item = get_item(&conn, id).unwrap();
+            item.scheduler_state = state;
+            assert!(update_items(&mut conn, vec![item]).is_ok());
+            assert!(get_item(&conn, id).unwrap().scheduler_state == state);
+        }
+        // spaced-rs keeps its state in the other columns
+        let query = "SELECT scheduler_state FROM items WHERE id=?";
+        let json: Option<String> = conn.query_row(query, [id], |row| row.get(0)).unwrap();
+        assert!(json.is_none());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn edit_notes_keeps_history() {
+        let (db_path, cleanup) = create_temp_dir("edit_notes_keeps_history");
+        let mut conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        assert!(get_item(&conn, id).unwrap().notes.is_empty());
+
+        assert!(update_item_notes(&mut conn, id, "# hello\n- print it", 100).is_ok());
+        assert!(update_item_notes(&mut conn, id, "# hello\n- just print it", 200).is_ok());
+        // saving the same text again does not create a new revision
+        assert!(update_item_notes(&mut conn, id, "# hello\n- just print it", 300).is_ok());
+        assert!(get_item(&conn, id).unwrap().notes == "# hello\n- just print it");
+
+        let history = get_note_history(&conn, id).unwrap();
+        assert!(history.len() == 2);
+        assert!(history[0].notes == "# hello\n- print it");
+        assert!(history[0].timestamp == 100);
+        assert!(history[1].timestamp == 200);
+        // notes of items that do not exist can't be saved
+        assert!(update_item_notes(&mut conn, id + 1, "nope", 400).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn count_due_new_and_total() {
+        let (db_path, cleanup) = create_temp_dir("count_due_new_and_total");
+        let mut conn = open_connection(&db_path).unwrap();
+        assert!(count_due_items(&conn, 100).unwrap() == 0);
+        assert!(count_inbox_items(&conn).unwrap() == 0);
+        assert!(count_items(&conn).unwrap() == 0);
+
+        insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/faktor", 1000),
+        )
+        .unwrap();
+        let urls = vec![
+            "https://open.kattis.com/problems/autori".into(),
+            "https://open.kattis.com/problems/sequences".into(),
+        ];
+        add_urls_to_inbox(&conn, urls).unwrap();
+        assert!(count_due_items(&conn, 100).unwrap() == 1);
+        assert!(count_due_items(&conn, 1000).unwrap() == 2);
+        assert!(count_inbox_items(&conn).unwrap() == 2);
+        assert!(count_items(&conn).unwrap() == 2);
+
+        // promoting moves one url from the new count to the total
+        promote_inbox_item(&mut conn, 1, &SchedulingConfig::default()).unwrap();
+        assert!(count_inbox_items(&conn).unwrap() == 1);
+        assert!(count_items(&conn).unwrap() == 3);
+        assert!(cleanup().is_ok());
+    }
+
+    /// util function that builds the log entry of reviewing {item} into {reviewed}
+    fn review_log(item: &Item, reviewed: &Item, grade: UserReview, timestamp: u64) -> ReviewLog {
+        ReviewLog {
+            id: 0,
+            item_id: item.id,
+            timestamp,
+            grade,
+            duration: Some(Duration::from_secs(90)),
+            interval_before: item.scheduling_data.interval,
+            interval_after: reviewed.scheduling_data.interval,
+            difficulty_before: item.scheduling_data.difficulty,
+            difficulty_after: reviewed.scheduling_data.difficulty,
+            verdict: None,
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn record_and_get_reviews() {
+        let (db_path, cleanup) = create_temp_dir("record_and_get_reviews");
+        let mut conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+
+        for (timestamp, grade) in [(100, UserReview::TooHard), (200, UserReview::TooEasy)] {
+            let item = get_item(&conn, id).unwrap();
+            let mut reviewed = get_item(&conn, id).unwrap();
+            reviewed.scheduling_data.interval = item.scheduling_data.interval * 2;
+            reviewed.scheduling_data.difficulty = item.scheduling_data.difficulty + 1.0;
+            reviewed.due = timestamp + 1000;
+            let log = review_log(&item, &reviewed, grade, timestamp);
+            assert!(record_review(&mut conn, reviewed, &log).is_ok());
+        }
+
+        // the item was updated
+        let item = get_item(&conn, id).unwrap();
+        assert!(item.due == 1200);
+        assert!(item.scheduling_data.interval == 4);
+        // and the history tells us how it got there
+        let reviews = get_item_reviews(&conn, id).unwrap();
+        assert!(reviews.len() == 2);
+        assert!(matches!(reviews[0].grade, UserReview::TooHard));
+        assert!(matches!(reviews[1].grade, UserReview::TooEasy));
+        assert!(reviews[0].timestamp == 100);
+        assert!(reviews[0].duration == Some(Duration::from_secs(90)));
+        assert!(reviews[0].interval_before == 1);
+        assert!(reviews[0].interval_after == 2);
+        assert!(reviews[1].interval_before == 2);
+        assert!(reviews[1].difficulty_after == 12.0);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn failed_item_update_records_no_review() {
+        let (db_path, cleanup) = create_temp_dir("failed_item_update_records_no_review");
+        let mut conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        let item = get_item(&conn, id).unwrap();
+        let mut missing = get_item(&conn, id).unwrap();
+        missing.id = id + 1;
+        let log = review_log(&item, &missing, UserReview::JustEnough, 100);
+        assert!(record_review(&mut conn, missing, &log).is_err());
+        assert!(get_item_reviews(&conn, id).unwrap().is_empty());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn item_status_controls_due_items() {
+        let (db_path, cleanup) = create_temp_dir("item_status_controls_due_items");
+        let conn = open_connection(&db_path).unwrap();
+        for problem in ["hello", "faktor", "autori", "sequences"] {
+            let url = format!("https://open.kattis.com/problems/{problem}");
+            insert_item(&conn, new_item(&url, 10)).unwrap();
+        }
+        set_item_status(&conn, 1, ItemStatus::Suspended).unwrap();
+        set_item_status(&conn, 2, ItemStatus::Buried(100)).unwrap();
+        set_item_status(&conn, 3, ItemStatus::Retired).unwrap();
+        assert!(set_item_status(&conn, 5, ItemStatus::Retired).is_err());
+
+        let due_ids = |timestamp| -> Vec<ItemId> {
+            let items = get_due_items(&conn, timestamp).unwrap();
+            items.iter().map(|item| item.id).collect()
+        };
+        assert!(due_ids(50) == vec![4]);
+        assert!(count_due_items(&conn, 50).unwrap() == 1);
+        assert!(
+            get_due_items_with_tags(&conn, 50, &[], false)
+                .unwrap()
+                .len()
+                == 1
+        );
+        let suspended = get_suspended_items(&conn, 50).unwrap();
+        let statuses: Vec<ItemStatus> = suspended.iter().map(|item| item.status).collect();
+        assert!(
+            statuses
+                == vec![
+                    ItemStatus::Suspended,
+                    ItemStatus::Buried(100),
+                    ItemStatus::Retired
+                ]
+        );
+        // the buried item is back the next day
+        assert!(due_ids(100) == vec![2, 4]);
+        assert!(get_suspended_items(&conn, 100).unwrap().len() == 2);
+        // and the suspended one once we unsuspend it
+        set_item_status(&conn, 1, ItemStatus::Active).unwrap();
+        assert!(due_ids(50) == vec![1, 4]);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn undo_review_restores_item() {
+        let (db_path, cleanup) = create_temp_dir("undo_review_restores_item");
+        let mut conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        let item = get_item(&conn, id).unwrap();
+        let mut reviewed = get_item(&conn, id).unwrap();
+        reviewed.scheduling_data.interval = 6;
+        reviewed.scheduling_data.times_reviewed = 1;
+        reviewed.scheduler_state = SchedulerState::Leitner { box_index: 1 };
+        reviewed.due = 1000;
+        let log = review_log(&item, &reviewed, UserReview::TooEasy, 100);
+        let review_id = record_review(&mut conn, reviewed, &log).unwrap();
+        let solution = Solution {
+            id: 0,
+            item_id: id,
+            review_id: Some(review_id),
+            language: "py".into(),
+            source: "print('Hello World!')".into(),
+            timestamp: 100,
+        };
+        add_solution(&conn, &solution).unwrap();
+
+        assert!(undo_review(&mut conn, item, review_id).is_ok());
+        let item = get_item(&conn, id).unwrap();
+        assert!(item.due == 10);
+        assert!(item.scheduling_data.interval == 1);
+        assert!(item.scheduling_data.times_reviewed == 0);
+        assert!(item.scheduler_state == SchedulerState::SpacedRs);
+        assert!(get_item_reviews(&conn, id).unwrap().is_empty());
+        // the solution stays in the archive
+        let solutions = get_item_solutions(&conn, id).unwrap();
+        assert!(solutions.len() == 1 && solutions[0].review_id.is_none());
+        // a review can only be taken back once
+        let item = get_item(&conn, id).unwrap();
+        assert!(undo_review(&mut conn, item, review_id).is_err());
+        assert!(cleanup().is_ok());
+    }
+
+    /// util function that logs a review of item {item_id} at {timestamp} without touching the item
+    fn log_review_at(conn: &mut Connection, item_id: ItemId, timestamp: u64, millis: Option<u64>) {
+        let item = get_item(conn, item_id).unwrap();
+        let log = ReviewLog {
+            id: 0,
+            item_id,
+            timestamp,
+            grade: UserReview::JustEnough,
+            duration: millis.map(Duration::from_millis),
+            interval_before: 1,
+            interval_after: 1,
+            difficulty_before: 10.0,
+            difficulty_after: 10.0,
+            verdict: None,
+        };
+        record_review(conn, item, &log).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn reviews_per_day_and_solve_time() {
+        let (db_path, cleanup) = create_temp_dir("reviews_per_day_and_solve_time");
+        let mut conn = open_connection(&db_path).unwrap();
+        let id = insert_item(
+            &conn,
+            new_item("https://open.kattis.com/problems/hello", 10),
+        )
+        .unwrap();
+        assert!(average_solve_time(&conn).unwrap().is_none());
+
+        let day = 19000 * SECONDS_PER_DAY;
+        log_review_at(&mut conn, id, day + 10, Some(60_000));
+        log_review_at(&mut conn, id, day + 20, None);
+        log_review_at(&mut conn, id, day + 2 * SECONDS_PER_DAY, Some(120_000));
+        // outside of the window
+        log_review_at(&mut conn, id, day + 5 * SECONDS_PER_DAY, Some(1));
+
+        assert!(reviews_per_day(&conn, 19000, 3).unwrap() == vec![2, 0, 1]);
+        assert!(reviews_per_day(&conn, 18999, 2).unwrap() == vec![0, 2]);
+        // untimed reviews don't count towards the average
+        let average = average_solve_time(&conn).unwrap().unwrap();
+        assert!(average == Duration::from_millis((60_000 + 120_000 + 1) / 3));
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn reviews_done_today() {
+        let (db_path, cleanup) = create_temp_dir("reviews_done_today");
+        let mut conn = open_connection(&db_path).unwrap();
+        let now = 19000 * SECONDS_PER_DAY + 100;
+        for problem in ["hello", "faktor"] {
+            let url = format!("https://open.kattis.com/problems/{problem}");
+            insert_item(&conn, new_item(&url, 10)).unwrap();
+        }
+        // hello was first reviewed yesterday, faktor today
+        log_review_at(&mut conn, 1, now - SECONDS_PER_DAY, Some(60_000));
+        log_review_at(&mut conn, 1, now, Some(120_000));
+        log_review_at(&mut conn, 2, now + 10, None);
+        log_review_at(&mut conn, 2, now + 20, Some(30_000));
+        let done = done_today(&conn, now).unwrap();
+        assert!(done.reviews == 2);
+        assert!(done.new == 1);
+        assert!(done.time == Duration::from_millis(150_000));
+        assert!(done_today(&conn, now + SECONDS_PER_DAY).unwrap() == DoneToday::default());
+
+        let averages = average_solve_times(&conn).unwrap();
+        assert!(averages[&1] == Duration::from_secs(90));
+        assert!(averages[&2] == Duration::from_secs(30));
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn forecast_of_due_items() {
+        let (db_path, cleanup) = create_temp_dir("forecast_of_due_items");
+        let conn = open_connection(&db_path).unwrap();
+        let now = 19000 * SECONDS_PER_DAY + 100;
+        let dues = [
+            now - 3 * SECONDS_PER_DAY, // overdue
+            now + 10,                  // later today
+            now + SECONDS_PER_DAY,
+            now + SECONDS_PER_DAY + 1,
+            now + 3 * SECONDS_PER_DAY, // past the forecast
+        ];
+        for (i, due) in dues.into_iter().enumerate() {
+            insert_item(
+                &conn,
+                new_item(&format!("https://open.kattis.com/problems/{i}"), due),
+            )
+            .unwrap();
+        }
+        assert!(due_forecast(&conn, now, 3).unwrap() == vec![2, 2, 0]);
+        // suspended items never come due, buried ones once they are back
+        set_item_status(&conn, 1, ItemStatus::Suspended).unwrap();
+        set_item_status(&conn, 2, ItemStatus::Buried(now + SECONDS_PER_DAY)).unwrap();
+        assert!(due_forecast(&conn, now, 3).unwrap() == vec![0, 3, 0]);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn retention_overall_and_per_tag() {
+        let (db_path, cleanup) = create_temp_dir("retention_overall_and_per_tag");
+        let mut conn = open_connection(&db_path).unwrap();
+        assert!(retention_rate(&conn).unwrap().is_none());
+
+        let mut graphs = new_item("https://open.kattis.com/problems/graphs", 10);
+        graphs.tags = vec!["graphs".into()];
+        let mut dp = new_item("https://open.kattis.com/problems/dp", 10);
+        dp.tags = vec!["dp".into(), "graphs".into()];
+        let mut unreviewed = new_item("https://open.kattis.com/problems/new", 10);
+        unreviewed.tags = vec!["strings".into()];
+        let ids: Vec<ItemId> = [graphs, dp, unreviewed]
+            .into_iter()
+            .map(|item| insert_item(&conn, item).unwrap())
+            .collect();
+        // 1 of the 4 reviews of graphs and 3 of the 4 of dp were graded hard
+        for (id, n_hard) in [(ids[0], 1), (ids[1], 3)] {
+            for i in 0..4 {
+                let grade = match i < n_hard {
+                    true => UserReview::TooHard,
+                    false => UserReview::JustEnough,
+                };
+                let mut item = get_item(&conn, id).unwrap();
+                // spaced-rs counts every review as recalled, the grade tells us better
+                item.scheduling_data.times_reviewed += 1;
+                item.scheduling_data.times_recalled += 1;
+                let log = review_log(&item, &item, grade, 100);
+                record_review(&mut conn, item, &log).unwrap();
+            }
+        }
+
+        assert!(retention_rate(&conn).unwrap() == Some(0.5));
+        let per_tag = retention_rate_per_tag(&conn).unwrap();
+        assert!(per_tag == vec![("dp".to_string(), 0.25), ("graphs".to_string(), 0.5)]);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn stats_heatmap_starts_on_a_monday() {
+        let (db_path, cleanup) = create_temp_dir("stats_heatmap_starts_on_a_monday");
+        let conn = open_connection(&db_path).unwrap();
+        // 2022-06-02 was a thursday
+        let now = 19145 * SECONDS_PER_DAY + 100;
+        let stats = get_stats(&conn, now).unwrap();
+        // 2022-05-30 is the monday of that week
+        assert!(stats.heatmap_start_day == 19142 - (HEATMAP_WEEKS - 1) * 7);
+        assert!(stats.reviews_per_day.len() as u64 == (HEATMAP_WEEKS - 1) * 7 + 4);
+        assert!(stats.due_forecast.len() == FORECAST_DAYS);
+        assert!(cleanup().is_ok());
+    }
 }
@@ -0,0 +1,89 @@
+//! This module contains a small line diff, used to compare two versions of a solution.
+
+/// A line of a diff between an old and a new text
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// the lines of {old} and {new} aligned along their longest common subsequence. Solutions are a
+/// few hundred lines at most, so the quadratic table is fine.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = vec![];
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| DiffLine::Removed(line)));
+    lines.extend(new[j..].iter().map(|line| DiffLine::Added(line)));
+    lines
+}
+
+/// {lines} in the +/- format of a unified diff, without hunk headers
+pub fn format_diff(lines: &[DiffLine<'_>]) -> String {
+    lines
+        .iter()
+        .map(|line| match line {
+            DiffLine::Same(line) => format!("  {line}\n"),
+            DiffLine::Removed(line) => format!("- {line}\n"),
+            DiffLine::Added(line) => format!("+ {line}\n"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiffLine::*;
+
+    #[test]
+    fn diff_changed_lines() {
+        let old = "read n\nloop\n  brute force\nprint\n";
+        let new = "read n\nsort\nloop\n  two pointers\nprint\n";
+        assert!(
+            diff_lines(old, new)
+                == vec![
+                    Same("read n"),
+                    Added("sort"),
+                    Same("loop"),
+                    Removed("  brute force"),
+                    Added("  two pointers"),
+                    Same("print"),
+                ]
+        );
+        assert!(format_diff(&diff_lines("a\nb", "a\nc")) == "  a\n- b\n+ c\n");
+    }
+
+    #[test]
+    fn diff_against_nothing() {
+        assert!(diff_lines("", "a\nb") == vec![Added("a"), Added("b")]);
+        assert!(diff_lines("a", "") == vec![Removed("a")]);
+        assert!(diff_lines("", "").is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! This module picks the inbox entries the daily drip promotes to review items, so new problems
+//! keep flowing in without pressing the promote key.
+
+// external imports
+use url::Url;
+// internal imports
+use crate::{config::DripBalance, planner, types::URLItem};
+
+/// what entries are balanced by, None for entries that only compete with each other
+fn group(entry: &URLItem, balance: DripBalance) -> Option<String> {
+    match balance {
+        DripBalance::Queue => None,
+        // tags are sorted, so an entry counts towards its first tag in alphabetical order
+        DripBalance::Tags => entry.tags.first().cloned(),
+        DripBalance::Sites => Url::parse(&entry.url)
+            .ok()
+            .and_then(|url| url.host_str().map(String::from)),
+    }
+}
+
+/// the ids of up to {n} of the {entries} (in queue order) to promote. With a balance the groups
+/// take turns, in the order their first entry is queued, each giving up its entries in queue order.
+pub fn pick_entries(entries: &[URLItem], n: usize, balance: DripBalance) -> Vec<u64> {
+    let entries = entries.iter().collect();
+    planner::round_robin(entries, |entry| group(entry, balance))
+        .into_iter()
+        .take(n)
+        .map(|entry| entry.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, url: &str, tags: &[&str]) -> URLItem {
+        URLItem {
+            id,
+            url: url.into(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn entries() -> Vec<URLItem> {
+        vec![
+            entry(1, "https://open.kattis.com/problems/hello", &["dp"]),
+            entry(
+                2,
+                "https://open.kattis.com/problems/faktor",
+                &["dp", "math"],
+            ),
+            entry(3, "https://codeforces.com/problemset/problem/4/A", &[]),
+            entry(4, "https://open.kattis.com/problems/autori", &["graphs"]),
+            entry(
+                5,
+                "https://atcoder.jp/contests/abc100/tasks/abc100_a",
+                &["dp"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn pick_from_the_top() {
+        assert!(pick_entries(&entries(), 3, DripBalance::Queue) == vec![1, 2, 3]);
+        assert!(pick_entries(&entries(), 10, DripBalance::Queue) == vec![1, 2, 3, 4, 5]);
+        assert!(pick_entries(&entries(), 0, DripBalance::Queue).is_empty());
+        assert!(pick_entries(&[], 3, DripBalance::Tags).is_empty());
+    }
+
+    #[test]
+    fn groups_take_turns() {
+        // dp, untagged and graphs, then dp again
+        assert!(pick_entries(&entries(), 4, DripBalance::Tags) == vec![1, 3, 4, 2]);
+        // kattis, codeforces and atcoder, then kattis again
+        assert!(pick_entries(&entries(), 4, DripBalance::Sites) == vec![1, 3, 5, 2]);
+        assert!(pick_entries(&entries(), 10, DripBalance::Sites) == vec![1, 3, 5, 2, 4]);
+    }
+}
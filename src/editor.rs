@@ -0,0 +1,122 @@
+//! This module lets the user edit text in their own $EDITOR. The tui gives up the terminal while
+//! the editor runs and takes it back once the editor exits.
+
+// stdlib imports
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+// external imports
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+// internal imports
+use crate::error::{AppError, AppResult};
+use crate::types::Term;
+
+/// editor used when $EDITOR is not set
+const FALLBACK_EDITOR: &str = "vi";
+
+/// a file of our own in the system's temporary directory, removed when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    /// creates the file with {text} in it, named like {file_name} with a random part before the
+    /// extension so the editor still recognizes the type. The file must not exist yet, so no one
+    /// else can have created it (or a link by that name) before us.
+    fn create(file_name: &str, text: &str) -> std::io::Result<Self> {
+        let name = Path::new(file_name);
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        loop {
+            let random = rand::random::<u64>();
+            let mut path = std::env::temp_dir().join(format!("{stem}-{random:016x}"));
+            if let Some(extension) = name.extension() {
+                path.set_extension(extension);
+            }
+            match options.open(&path) {
+                Ok(mut file) => {
+                    let temp_file = TempFile(path);
+                    file.write_all(text.as_bytes())?;
+                    return Ok(temp_file);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// opens {text} in $EDITOR through a temporary file named after {file_name} and returns the edited
+/// text. If the editor exits unsuccessfully the edit is discarded and {text} is returned unchanged.
+pub fn edit_in_editor(term: &mut Term, file_name: &str, text: &str) -> AppResult<String> {
+    let file = TempFile::create(file_name, text).map_err(AppError::EditorError)?;
+
+    suspend_tui(term)?;
+    let status = run_editor(&file.0);
+    // we take back the terminal even if the editor could not be launched
+    resume_tui(term)?;
+
+    let status = status.map_err(AppError::EditorError)?;
+    if !status.success() {
+        return Ok(text.to_string());
+    }
+    std::fs::read_to_string(&file.0).map_err(AppError::EditorError)
+}
+
+/// runs $EDITOR (which may contain arguments, e.g. "code --wait") on {path} and waits for it
+fn run_editor(path: &Path) -> std::io::Result<ExitStatus> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| FALLBACK_EDITOR.into());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or(FALLBACK_EDITOR);
+    Command::new(program).args(parts).arg(path).status()
+}
+
+/// leaves the alternate screen and raw mode, the reverse of the setup done in main
+fn suspend_tui(term: &mut Term) -> AppResult<()> {
+    disable_raw_mode()?;
+    execute!(
+        term.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    term.show_cursor()?;
+    Ok(())
+}
+
+/// re-enters the tui after suspend_tui and forces a full redraw on the next frame
+fn resume_tui(term: &mut Term) -> AppResult<()> {
+    enable_raw_mode()?;
+    execute!(term.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    term.hide_cursor()?;
+    term.clear()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_files_are_unique_and_cleaned_up() {
+        let first = TempFile::create("spaced-programming-notes-1.md", "# hello").unwrap();
+        let second = TempFile::create("spaced-programming-notes-1.md", "").unwrap();
+        assert!(first.0 != second.0);
+        assert!(first.0.extension().unwrap() == "md");
+        assert!(std::fs::read_to_string(&first.0).unwrap() == "# hello");
+        let path = first.0.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+}
@@ -10,9 +10,15 @@ use crate::types::SchemaVersion;
 pub type AppResult<T> = std::result::Result<T, AppError>;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AppError {
     DatabaseError(DatabaseErrorSource),
     TUIError(std::io::Error),
+    EditorError(std::io::Error),
+    InputError(std::io::Error),
+    ConfigError(String),
+    FeedError(String),
+    JudgeError(std::io::Error),
 }
 
 impl Display for AppError {
@@ -20,6 +26,11 @@ impl Display for AppError {
         match self {
             AppError::DatabaseError(err) => write!(f, "DatabaseError: {err})"),
             AppError::TUIError(err) => write!(f, "TUIError: {err})"),
+            AppError::EditorError(err) => write!(f, "EditorError: {err})"),
+            AppError::InputError(err) => write!(f, "InputError: {err})"),
+            AppError::ConfigError(err) => write!(f, "ConfigError: {err})"),
+            AppError::FeedError(err) => write!(f, "FeedError: {err})"),
+            AppError::JudgeError(err) => write!(f, "JudgeError: {err})"),
         }
     }
 }
@@ -27,10 +38,17 @@ impl Display for AppError {
 pub type DatabaseResult<T> = std::result::Result<T, DatabaseErrorSource>;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum DatabaseErrorSource {
     SQLError(RusqliteError),
     InvalidSchemaError(SchemaVersion),
     DirCreationError(std::io::Error),
+    BackupError(std::io::Error),
+    MigrationError {
+        from: SchemaVersion,
+        to: SchemaVersion,
+        source: RusqliteError,
+    },
 }
 
 impl From<std::io::Error> for AppError {
@@ -39,15 +57,26 @@ impl From<std::io::Error> for AppError {
     }
 }
 
+impl From<DatabaseErrorSource> for AppError {
+    fn from(err: DatabaseErrorSource) -> Self {
+        AppError::DatabaseError(err)
+    }
+}
+
 impl Display for DatabaseErrorSource {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DatabaseErrorSource::SQLError(err) => write!(f, "SQLError: {err}"),
             DatabaseErrorSource::InvalidSchemaError(version)=> write!(
                 f,
-                "InvalidSchemaError: schema version was {version}. Was not equal to expected constant"
+                "InvalidSchemaError: schema version was {version}. Is newer than the latest known schema"
             ),
             DatabaseErrorSource::DirCreationError(err) => write!(f, "DirCreationError: {err})"),
+            DatabaseErrorSource::BackupError(err) => write!(f, "BackupError: {err})"),
+            DatabaseErrorSource::MigrationError { from, to, source } => write!(
+                f,
+                "MigrationError: could not migrate schema from version {from} to {to}: {source}"
+            ),
         }
     }
 }
@@ -0,0 +1,252 @@
+//! This module contains the feed subsystem. RSS 2.0 and Atom feeds are fetched, parsed, and the
+//! links of entries we have not seen before end up at the bottom of the inbox.
+
+// stdlib imports
+use std::time::Duration;
+// external imports
+use roxmltree::{Document, Node};
+use rusqlite::Connection;
+// internal imports
+use crate::{
+    database,
+    error::{AppError, AppResult},
+    types::{Feed, FeedEntry},
+};
+
+/// a feed that does not answer within this time is skipped until the next sync
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// the outcome of syncing one feed: the number of problems it added or why it failed
+pub type SyncResult = (Feed, AppResult<usize>);
+
+/// the entries of an rss 2.0 or atom document, in document order. Entries without a link are
+/// skipped, entries without a guid (or atom id) are identified by their link.
+pub fn parse_feed(xml: &str) -> Result<Vec<FeedEntry>, String> {
+    let doc = Document::parse(xml).map_err(|err| err.to_string())?;
+    let root = doc.root_element();
+    let entries = match root.tag_name().name() {
+        "rss" => root
+            .children()
+            .filter(|node| node.has_tag_name("channel"))
+            .flat_map(|channel| channel.children().filter(|node| node.has_tag_name("item")))
+            .filter_map(rss_entry)
+            .collect(),
+        "feed" => root
+            .children()
+            .filter(|node| node.has_tag_name("entry"))
+            .filter_map(atom_entry)
+            .collect(),
+        other => return Err(format!("<{other}> is neither an rss nor an atom feed")),
+    };
+    Ok(entries)
+}
+
+/// the trimmed text of the first child of {node} called {name}, if it has any
+fn child_text(node: Node<'_, '_>, name: &str) -> Option<String> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn rss_entry(item: Node<'_, '_>) -> Option<FeedEntry> {
+    let link = child_text(item, "link")?;
+    let guid = child_text(item, "guid").unwrap_or_else(|| link.clone());
+    Some(FeedEntry { guid, link })
+}
+
+fn atom_entry(entry: Node<'_, '_>) -> Option<FeedEntry> {
+    // an entry may link to several things, the alternate link is the entry itself
+    let link = entry
+        .children()
+        .filter(|child| child.has_tag_name("link"))
+        .find(|link| link.attribute("rel").is_none_or(|rel| rel == "alternate"))
+        .and_then(|link| link.attribute("href"))
+        .map(|href| href.trim().to_string())
+        .filter(|href| !href.is_empty())?;
+    let guid = child_text(entry, "id").unwrap_or_else(|| link.clone());
+    Some(FeedEntry { guid, link })
+}
+
+/// the document behind {source}, which is either an http(s) url or a local file
+/// (optionally prefixed with file://)
+fn fetch_feed(source: &str) -> AppResult<String> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        ureq::get(source)
+            .timeout(FETCH_TIMEOUT)
+            .call()
+            .map_err(|err| AppError::FeedError(format!("{source}: {err}")))?
+            .into_string()
+            .map_err(|err| AppError::FeedError(format!("{source}: {err}")))
+    } else {
+        let path = source.strip_prefix("file://").unwrap_or(source);
+        std::fs::read_to_string(path).map_err(|err| AppError::FeedError(format!("{source}: {err}")))
+    }
+}
+
+/// fetches {feed} and enqueues its new entries, returns how many there were
+pub fn sync_feed(conn: &mut Connection, feed: &Feed, now: u64) -> AppResult<usize> {
+    let xml = fetch_feed(&feed.url)?;
+    let entries =
+        parse_feed(&xml).map_err(|err| AppError::FeedError(format!("{}: {err}", feed.url)))?;
+    Ok(database::add_feed_entries(conn, feed.id, &entries, now)?)
+}
+
+/// syncs every feed. One broken feed does not keep us from syncing the others, so failures are
+/// reported per feed.
+pub fn sync_feeds(conn: &mut Connection, now: u64) -> AppResult<Vec<SyncResult>> {
+    let feeds = database::get_feeds(conn)?;
+    Ok(feeds
+        .into_iter()
+        .map(|feed| {
+            let result = sync_feed(conn, &feed, now);
+            (feed, result)
+        })
+        .collect())
+}
+
+/// a one line summary of a sync, for the main screen
+pub fn summarize(results: &[SyncResult]) -> String {
+    let added: usize = results
+        .iter()
+        .filter_map(|(_, res)| res.as_ref().ok())
+        .sum();
+    let failed = results.iter().filter(|(_, res)| res.is_err()).count();
+    match failed {
+        0 => format!("feeds: {added} new problems"),
+        _ => format!("feeds: {added} new problems, {failed} feeds failed to sync"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::create_temp_dir;
+    use serial_test::serial;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>new problems</title>
+    <link>https://open.kattis.com</link>
+    <item>
+      <title>Hello World!</title>
+      <link>https://open.kattis.com/problems/hello</link>
+      <guid>kattis-hello</guid>
+    </item>
+    <item>
+      <title>Faktor</title>
+      <link> https://open.kattis.com/problems/faktor </link>
+    </item>
+    <item>
+      <title>an announcement without a link</title>
+    </item>
+  </channel>
+</rss>"#;
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>new problems</title>
+  <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+  <entry>
+    <title>Autori</title>
+    <link rel="edit" href="https://example.com/edit/autori"/>
+    <link rel="alternate" href="https://open.kattis.com/problems/autori"/>
+    <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+  </entry>
+  <entry>
+    <title>Hello World!</title>
+    <link href="https://open.kattis.com/problems/hello"/>
+  </entry>
+</feed>"#;
+
+    #[test]
+    fn parse_rss() {
+        let entries = parse_feed(RSS).unwrap();
+        assert!(entries.len() == 2);
+        assert!(entries[0].guid == "kattis-hello");
+        assert!(entries[0].link == "https://open.kattis.com/problems/hello");
+        // no guid, the link identifies the entry
+        assert!(entries[1].guid == "https://open.kattis.com/problems/faktor");
+        assert!(entries[1].link == "https://open.kattis.com/problems/faktor");
+    }
+
+    #[test]
+    fn parse_atom() {
+        let entries = parse_feed(ATOM).unwrap();
+        assert!(entries.len() == 2);
+        assert!(entries[0].guid == "urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a");
+        assert!(entries[0].link == "https://open.kattis.com/problems/autori");
+        assert!(entries[1].guid == "https://open.kattis.com/problems/hello");
+    }
+
+    #[test]
+    fn parse_rejects_other_documents() {
+        assert!(parse_feed("<html><body></body></html>").is_err());
+        assert!(parse_feed("not xml at all").is_err());
+    }
+
+    /// util function that serves {body} over http on a local port, once per expected request
+    fn serve(body: &'static str, n_requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(n_requests) {
+                let mut stream = stream.unwrap();
+                // the request is small enough to arrive in one read
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request).unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{addr}/feed.xml")
+    }
+
+    #[test]
+    #[serial]
+    fn sync_enqueues_new_entries_once() {
+        let (db_path, cleanup) = create_temp_dir("sync_enqueues_new_entries_once");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        let url = serve(RSS, 2);
+        database::add_feed(&conn, &url).unwrap();
+
+        let results = sync_feeds(&mut conn, 100).unwrap();
+        assert!(matches!(results[0].1, Ok(2)));
+        assert!(summarize(&results) == "feeds: 2 new problems");
+        // the entries are remembered even after they leave the inbox
+        let inbox = database::get_n_urls_from_inbox(&conn, 10).unwrap();
+        assert!(inbox.len() == 2);
+        database::remove_new_item(&conn, inbox[0].id).unwrap();
+        let results = sync_feeds(&mut conn, 200).unwrap();
+        assert!(matches!(results[0].1, Ok(0)));
+        assert!(database::count_inbox_items(&conn).unwrap() == 1);
+        assert!(database::get_feeds(&conn).unwrap()[0].last_synced == Some(200));
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn broken_feed_does_not_stop_sync() {
+        let (db_path, cleanup) = create_temp_dir("broken_feed_does_not_stop_sync");
+        let mut conn = database::open_connection(&db_path).unwrap();
+        let atom_file = db_path.with_file_name("atom.xml");
+        std::fs::write(&atom_file, ATOM).unwrap();
+        database::add_feed(&conn, "/no/such/feed.xml").unwrap();
+        database::add_feed(&conn, &format!("file://{}", atom_file.display())).unwrap();
+
+        let results = sync_feeds(&mut conn, 100).unwrap();
+        assert!(matches!(results[0].1, Err(AppError::FeedError(_))));
+        assert!(matches!(results[1].1, Ok(2)));
+        assert!(summarize(&results) == "feeds: 2 new problems, 1 feeds failed to sync");
+        // a failed sync does not count as synced
+        assert!(database::get_feeds(&conn).unwrap()[0].last_synced.is_none());
+        assert!(cleanup().is_ok());
+    }
+}
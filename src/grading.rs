@@ -0,0 +1,182 @@
+//! This module contains the grading policy: the grade a review most likely deserves, judging by
+//! how long solving took compared to the problem's target time and what the local judge said.
+//! It is only a suggestion, the eval screen pre-selects it and we are free to pick another.
+
+// stdlib imports
+use std::time::Duration;
+// internal imports
+use crate::{
+    config::GradingConfig,
+    scheduling::copy_review,
+    types::{Item, UserReview, Verdict},
+};
+
+/// solving a problem in at most this fraction of its target time makes it easy
+pub const EASY_RATIO: f64 = 0.5;
+/// taking more than this fraction of its target time makes a problem hard
+pub const HARD_RATIO: f64 = 1.5;
+
+/// the time we want to solve {item} in: its own target, else the target for its difficulty, else
+/// the default target
+pub fn target_time(item: &Item, config: &GradingConfig) -> Duration {
+    let minutes = item
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.difficulty.as_deref())
+        .and_then(|difficulty| difficulty_target(difficulty, config))
+        .unwrap_or(config.target_minutes);
+    item.target
+        .unwrap_or(Duration::from_secs(minutes as u64 * 60))
+}
+
+/// the target of {difficulty}: the one listed for exactly that difficulty, or for numeric
+/// difficulties the one of the smallest numeric key that is at least as hard
+fn difficulty_target(difficulty: &str, config: &GradingConfig) -> Option<u32> {
+    if let Some(minutes) = config.difficulty_targets.get(difficulty) {
+        return Some(*minutes);
+    }
+    let difficulty: f64 = difficulty.parse().ok()?;
+    config
+        .difficulty_targets
+        .iter()
+        .filter_map(|(key, minutes)| Some((key.parse::<f64>().ok()?, *minutes)))
+        .filter(|(bound, _)| difficulty <= *bound)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, minutes)| minutes)
+}
+
+/// the grade to suggest for a review that took {duration} (None if it was not timed) with
+/// {target} as the target time. A solution the judge rejected is always hard, without a duration
+/// there is nothing to go on and we suggest normal.
+pub fn suggest_grade(
+    duration: Option<Duration>,
+    target: Duration,
+    verdict: Option<Verdict>,
+) -> UserReview {
+    if verdict.is_some_and(|verdict| verdict != Verdict::Accepted) {
+        return UserReview::TooHard;
+    }
+    let Some(duration) = duration else {
+        return UserReview::JustEnough;
+    };
+    let ratio = duration.as_secs_f64() / target.as_secs_f64().max(1.0);
+    if ratio <= EASY_RATIO {
+        UserReview::TooEasy
+    } else if ratio <= HARD_RATIO {
+        UserReview::JustEnough
+    } else {
+        UserReview::TooHard
+    }
+}
+
+/// the name of {grade} on the easy, normal, hard scale
+pub fn grade_name(grade: &UserReview) -> &'static str {
+    match grade {
+        UserReview::TooEasy => "easy",
+        UserReview::JustEnough => "normal",
+        UserReview::TooHard => "hard",
+    }
+}
+
+/// the grade next to {grade} on the easy, normal, hard scale. {harder} moves towards hard and
+/// stops at either end.
+pub fn shift_grade(grade: &UserReview, harder: bool) -> UserReview {
+    match (grade, harder) {
+        (UserReview::TooEasy, true) | (UserReview::TooHard, false) => UserReview::JustEnough,
+        (UserReview::JustEnough, true) => UserReview::TooHard,
+        (UserReview::JustEnough, false) => UserReview::TooEasy,
+        (grade, _) => copy_review(grade),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::new_item;
+    use crate::types::ProblemMetadata;
+
+    fn minutes(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn suggestion_follows_duration() {
+        let target = minutes(30);
+        let suggest = |duration| suggest_grade(duration, target, None);
+        assert!(matches!(suggest(Some(minutes(10))), UserReview::TooEasy));
+        assert!(matches!(suggest(Some(minutes(15))), UserReview::TooEasy));
+        assert!(matches!(suggest(Some(minutes(16))), UserReview::JustEnough));
+        assert!(matches!(suggest(Some(minutes(45))), UserReview::JustEnough));
+        assert!(matches!(suggest(Some(minutes(46))), UserReview::TooHard));
+        // not timed
+        assert!(matches!(suggest(None), UserReview::JustEnough));
+    }
+
+    #[test]
+    fn rejected_solutions_are_hard() {
+        let target = minutes(30);
+        for verdict in [
+            Verdict::WrongAnswer,
+            Verdict::TimeLimitExceeded,
+            Verdict::RuntimeError,
+            Verdict::CompileError,
+        ] {
+            let grade = suggest_grade(Some(minutes(5)), target, Some(verdict));
+            assert!(matches!(grade, UserReview::TooHard));
+        }
+        let grade = suggest_grade(Some(minutes(5)), target, Some(Verdict::Accepted));
+        assert!(matches!(grade, UserReview::TooEasy));
+        let grade = suggest_grade(None, target, Some(Verdict::Accepted));
+        assert!(matches!(grade, UserReview::JustEnough));
+    }
+
+    #[test]
+    fn targets_by_item_and_difficulty() {
+        let config = GradingConfig {
+            target_minutes: 30,
+            difficulty_targets: [("Easy", 15), ("3.0", 20), ("6.0", 60), ("1600", 45)]
+                .into_iter()
+                .map(|(difficulty, minutes)| (difficulty.to_string(), minutes))
+                .collect(),
+        };
+        let item = |difficulty: Option<&str>, target: Option<Duration>| {
+            let mut metadata = ProblemMetadata::new("kattis", "hello", None, "hello");
+            metadata.difficulty = difficulty.map(String::from);
+            Item {
+                metadata: Some(metadata),
+                target,
+                ..new_item("", 0)
+            }
+        };
+        assert!(target_time(&item(None, None), &config) == minutes(30));
+        assert!(target_time(&item(Some("Easy"), None), &config) == minutes(15));
+        assert!(target_time(&item(Some("1.4"), None), &config) == minutes(20));
+        assert!(target_time(&item(Some("4.1"), None), &config) == minutes(60));
+        assert!(target_time(&item(Some("1200"), None), &config) == minutes(45));
+        // harder than every bound
+        assert!(target_time(&item(Some("2400"), None), &config) == minutes(30));
+        assert!(target_time(&item(Some("Medium"), None), &config) == minutes(30));
+        let own_target = Some(minutes(90));
+        assert!(target_time(&item(Some("Easy"), own_target), &config) == minutes(90));
+    }
+
+    #[test]
+    fn shifting_stops_at_the_ends() {
+        assert!(matches!(
+            shift_grade(&UserReview::TooEasy, true),
+            UserReview::JustEnough
+        ));
+        assert!(matches!(
+            shift_grade(&UserReview::JustEnough, true),
+            UserReview::TooHard
+        ));
+        assert!(matches!(
+            shift_grade(&UserReview::TooHard, true),
+            UserReview::TooHard
+        ));
+        assert!(matches!(
+            shift_grade(&UserReview::TooEasy, false),
+            UserReview::TooEasy
+        ));
+    }
+}
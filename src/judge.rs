@@ -0,0 +1,501 @@
+//! This module contains the local judge. It builds a solution with the command configured for its
+//! language, runs it on the sample tests of an item and compares the output like an online judge
+//! would, so we can test without leaving the review.
+
+// stdlib imports
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+// internal imports
+use crate::{
+    config::{Comparison, JudgeConfig, LanguageConfig},
+    error::{AppError, AppResult},
+    types::{JudgeReport, SampleTest, Verdict},
+};
+
+/// how much of an output or error message ends up in a report
+const MAX_DETAILS_LEN: usize = 500;
+
+/// a directory of its own for the files of one judge run, removed with everything in it when
+/// dropped, however the run ends
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    /// creates the directory under the system's temporary directory. The name is random and the
+    /// directory must not exist yet, so no one else can have put files in it.
+    fn create() -> std::io::Result<Self> {
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        loop {
+            let name = format!("spaced-programming-judge-{:016x}", rand::random::<u64>());
+            let path = std::env::temp_dir().join(name);
+            match builder.create(&path) {
+                Ok(()) => return Ok(WorkDir(path)),
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// the newest file in {dir} with the extension of one of {languages}, the one we are most likely
+/// working on
+pub fn find_solution(
+    dir: &Path,
+    languages: &BTreeMap<String, LanguageConfig>,
+) -> std::io::Result<Option<PathBuf>> {
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let known = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| languages.contains_key(ext));
+        if !known || !path.is_file() {
+            continue;
+        }
+        let modified = path.metadata()?.modified()?;
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, path));
+        }
+    }
+    Ok(newest.map(|(_, path)| path))
+}
+
+/// whether {actual} is an acceptable answer when {expected} is the right one
+pub fn outputs_match(expected: &str, actual: &str, comparison: Comparison, tolerance: f64) -> bool {
+    let tokens = |text: &str| {
+        text.split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    match comparison {
+        Comparison::Exact => expected == actual,
+        Comparison::Whitespace => tokens(expected) == tokens(actual),
+        Comparison::Float => {
+            let (expected, actual) = (tokens(expected), tokens(actual));
+            expected.len() == actual.len()
+                && expected.iter().zip(&actual).all(|(e, a)| {
+                    match (e.parse::<f64>(), a.parse::<f64>()) {
+                        // absolute or relative error, whichever is more forgiving
+                        (Ok(e), Ok(a)) => (e - a).abs() <= tolerance * e.abs().max(1.0),
+                        _ => e == a,
+                    }
+                })
+        }
+    }
+}
+
+/// the program and arguments of {command}, split on whitespace with the placeholders filled in.
+/// A blank command has no program to run.
+fn command_line(command: &str, src: &Path, bin: &Path) -> std::io::Result<(String, Vec<String>)> {
+    let mut parts = command.split_whitespace().map(|part| {
+        part.replace("{src}", &src.to_string_lossy())
+            .replace("{bin}", &bin.to_string_lossy())
+    });
+    let program = parts.next().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("no program in the command {command:?}"),
+        )
+    })?;
+    Ok((program, parts.collect()))
+}
+
+fn truncated(text: &str) -> String {
+    match text.char_indices().nth(MAX_DETAILS_LEN) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// how a single run of the solution ended
+enum RunOutcome {
+    TimedOut,
+    Exited {
+        success: bool,
+        stdout: String,
+        stderr: String,
+    },
+}
+
+/// runs {program} with {args} and {input} on stdin, killing it once it exceeds {time_limit}
+fn run_with_limit(
+    program: &str,
+    args: &[String],
+    input: &str,
+    work_dir: &Path,
+    time_limit: Duration,
+) -> std::io::Result<RunOutcome> {
+    // files instead of pipes, a chatty solution can't fill them up and block
+    let stdout_path = work_dir.join("stdout");
+    let stderr_path = work_dir.join("stderr");
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(File::create(&stdout_path)?)
+        .stderr(File::create(&stderr_path)?)
+        .spawn()?;
+    // a solution that does not read its whole input must not block us, so we write from a thread
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if start.elapsed() > time_limit {
+            child.kill()?;
+            child.wait()?;
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    let _ = writer.join();
+    Ok(match status {
+        None => RunOutcome::TimedOut,
+        Some(status) => RunOutcome::Exited {
+            success: status.success(),
+            stdout: String::from_utf8_lossy(&std::fs::read(stdout_path)?).into_owned(),
+            stderr: String::from_utf8_lossy(&std::fs::read(stderr_path)?).into_owned(),
+        },
+    })
+}
+
+/// builds {solution} and runs it on every sample. Without samples there is nothing to judge,
+/// which is an error rather than a pass.
+pub fn judge(
+    solution: &Path,
+    samples: &[SampleTest],
+    config: &JudgeConfig,
+) -> AppResult<JudgeReport> {
+    let judge_error = |err: std::io::Error| AppError::JudgeError(err);
+    if samples.is_empty() {
+        return Err(judge_error(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "no sample tests",
+        )));
+    }
+    let extension = solution.extension().and_then(|ext| ext.to_str());
+    let language = extension
+        .and_then(|ext| config.languages.get(ext))
+        .ok_or_else(|| {
+            judge_error(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("no language configured for {}", solution.display()),
+            ))
+        })?;
+    let work_dir = WorkDir::create().map_err(judge_error)?;
+    let bin = work_dir.0.join("solution");
+    let report = |verdict, results, details: &str| JudgeReport {
+        solution: solution.to_path_buf(),
+        verdict,
+        results,
+        details: truncated(details.trim()),
+    };
+
+    if let Some(compile) = &language.compile {
+        let (program, args) = command_line(compile, solution, &bin).map_err(judge_error)?;
+        let compiled = Command::new(&program)
+            .args(&args)
+            .stdin(Stdio::null())
+            .output();
+        let details = match compiled {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => Some(String::from_utf8_lossy(&output.stderr).into_owned()),
+            Err(err) => Some(format!("could not run {program}: {err}")),
+        };
+        if let Some(details) = details {
+            return Ok(report(Verdict::CompileError, vec![], &details));
+        }
+    }
+
+    let (program, args) = command_line(&language.run, solution, &bin).map_err(judge_error)?;
+    let time_limit = Duration::from_millis(config.time_limit_ms);
+    let mut results = vec![];
+    let mut details = String::new();
+    for (i, sample) in samples.iter().enumerate() {
+        let run = run_with_limit(&program, &args, &sample.input, &work_dir.0, time_limit);
+        let (verdict, why) = match run {
+            Err(err) => (
+                Verdict::RuntimeError,
+                format!("could not run {program}: {err}"),
+            ),
+            Ok(RunOutcome::TimedOut) => (
+                Verdict::TimeLimitExceeded,
+                format!("took longer than {} ms", config.time_limit_ms),
+            ),
+            Ok(RunOutcome::Exited {
+                success: false,
+                stderr,
+                ..
+            }) => (Verdict::RuntimeError, stderr),
+            Ok(RunOutcome::Exited { stdout, .. }) => {
+                if outputs_match(
+                    &sample.output,
+                    &stdout,
+                    config.comparison,
+                    config.float_tolerance,
+                ) {
+                    (Verdict::Accepted, String::new())
+                } else {
+                    let expected = sample.output.trim();
+                    let actual = stdout.trim();
+                    (
+                        Verdict::WrongAnswer,
+                        format!("expected {expected:?}, got {actual:?}"),
+                    )
+                }
+            }
+        };
+        if verdict != Verdict::Accepted && details.is_empty() {
+            details = format!("sample {}: {why}", i + 1);
+        }
+        results.push(verdict);
+    }
+    let verdict = results
+        .iter()
+        .copied()
+        .find(|verdict| *verdict != Verdict::Accepted)
+        .unwrap_or(Verdict::Accepted);
+    Ok(report(verdict, results, &details))
+}
+
+/// the samples in {dir}: every `name.in` with a `name.ans` (or `name.out`) next to it, in file
+/// name order. This is how kattis and most contest packages ship them.
+pub fn import_samples(dir: &Path) -> std::io::Result<Vec<(String, String)>> {
+    let mut inputs: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    inputs.retain(|path| path.extension().is_some_and(|ext| ext == "in"));
+    inputs.sort();
+    let mut samples = vec![];
+    for input in inputs {
+        let answer = ["ans", "out"]
+            .iter()
+            .map(|ext| input.with_extension(ext))
+            .find(|path| path.is_file());
+        if let Some(answer) = answer {
+            samples.push((
+                std::fs::read_to_string(&input)?,
+                std::fs::read_to_string(answer)?,
+            ));
+        }
+    }
+    Ok(samples)
+}
+
+const INPUT_HEADER: &str = "=== input";
+const OUTPUT_HEADER: &str = "=== output";
+
+/// the samples as text to edit in the editor, see [samples_from_text]
+pub fn samples_to_text(samples: &[SampleTest]) -> String {
+    let mut text = format!(
+        "# sample tests: the input after each '{INPUT_HEADER}' line, its expected output after the\n\
+         # '{OUTPUT_HEADER}' line that follows. Paste as many as you like.\n"
+    );
+    // every part ends in a newline so the next header starts a line of its own
+    let part = |text: &str| match text.is_empty() || text.ends_with('\n') {
+        true => text.to_string(),
+        false => format!("{text}\n"),
+    };
+    for sample in samples {
+        text.push_str(&format!("{INPUT_HEADER}\n{}", part(&sample.input)));
+        text.push_str(&format!("{OUTPUT_HEADER}\n{}", part(&sample.output)));
+    }
+    if samples.is_empty() {
+        text.push_str(&format!("{INPUT_HEADER}\n{OUTPUT_HEADER}\n"));
+    }
+    text
+}
+
+/// the input/output pairs in {text}. Comment lines before the first header are ignored, as are
+/// samples without any input or output.
+pub fn samples_from_text(text: &str) -> Vec<(String, String)> {
+    let mut samples: Vec<(String, String)> = vec![];
+    // which part of the last sample we are reading
+    let mut in_output = None;
+    for line in text.lines() {
+        if line.trim_end() == INPUT_HEADER {
+            samples.push((String::new(), String::new()));
+            in_output = Some(false);
+        } else if line.trim_end() == OUTPUT_HEADER && in_output == Some(false) {
+            in_output = Some(true);
+        } else if let (Some(in_output), Some((input, output))) = (in_output, samples.last_mut()) {
+            let part = if in_output { output } else { input };
+            part.push_str(line);
+            part.push('\n');
+        }
+    }
+    samples.retain(|(input, output)| !input.trim().is_empty() || !output.trim().is_empty());
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::create_temp_dir;
+    use serial_test::serial;
+
+    #[test]
+    fn compare_outputs() {
+        use Comparison::*;
+        assert!(outputs_match("1 2\n", "1 2\n", Exact, 0.0));
+        assert!(!outputs_match("1 2\n", "1  2", Exact, 0.0));
+        assert!(outputs_match("1 2\n", "1  2", Whitespace, 0.0));
+        assert!(!outputs_match("1 2\n", "1 3", Whitespace, 0.0));
+        assert!(outputs_match(
+            "0.333333 yes",
+            "0.3333334 yes\n",
+            Float,
+            1e-6
+        ));
+        assert!(!outputs_match("0.333333", "0.34", Float, 1e-6));
+        // large numbers get a relative tolerance
+        assert!(outputs_match("1000000000.0", "1000000000.5", Float, 1e-6));
+        assert!(!outputs_match("1 2", "1", Float, 1e-6));
+    }
+
+    #[test]
+    fn samples_text_round_trip() {
+        let samples = vec![
+            SampleTest {
+                input: "1 2\n".into(),
+                output: "3\n".into(),
+            },
+            SampleTest {
+                input: "2\n5\n6".into(),
+                output: "11".into(),
+            },
+        ];
+        let text = samples_to_text(&samples);
+        assert!(
+            samples_from_text(&text)
+                == vec![
+                    ("1 2\n".to_string(), "3\n".to_string()),
+                    ("2\n5\n6\n".to_string(), "11\n".to_string())
+                ]
+        );
+        // the empty template has no samples
+        assert!(samples_from_text(&samples_to_text(&[])).is_empty());
+    }
+
+    #[test]
+    fn work_dirs_are_unique_and_cleaned_up() {
+        let (first, second) = (WorkDir::create().unwrap(), WorkDir::create().unwrap());
+        assert!(first.0 != second.0);
+        std::fs::write(first.0.join("solution"), "").unwrap();
+        let path = first.0.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn fill_in_command_lines() {
+        let (src, bin) = (Path::new("/tmp/a.cpp"), Path::new("/tmp/a"));
+        let (program, args) = command_line("g++ -O2 {src}  -o {bin}", src, bin).unwrap();
+        assert!(program == "g++");
+        assert!(args == vec!["-O2", "/tmp/a.cpp", "-o", "/tmp/a"]);
+        // a blank command is an error, not a panic
+        assert!(command_line("  ", src, bin).is_err());
+    }
+
+    /// util function that judges the shell script {script} on two samples that add two numbers
+    fn judge_script(name: &str, script: &str, config: &JudgeConfig) -> JudgeReport {
+        let (db_path, cleanup) = create_temp_dir(name);
+        std::fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+        let solution = db_path.with_file_name("solution.sh");
+        std::fs::write(&solution, script).unwrap();
+        let sample = |input: &str, output: &str| SampleTest {
+            input: input.into(),
+            output: output.into(),
+        };
+        let samples = [sample("1 2\n", "3\n"), sample("20 22\n", "42\n")];
+        let report = judge(&solution, &samples, config).unwrap();
+        assert!(cleanup().is_ok());
+        report
+    }
+
+    fn sh_config() -> JudgeConfig {
+        let mut config = JudgeConfig {
+            time_limit_ms: 300,
+            ..JudgeConfig::default()
+        };
+        let sh = LanguageConfig {
+            compile: Some("sh -n {src}".into()),
+            run: "sh {src}".into(),
+        };
+        config.languages.insert("sh".into(), sh);
+        config
+    }
+
+    #[test]
+    #[serial]
+    fn judge_verdicts() {
+        let config = sh_config();
+        let adds = "read a b\necho $((a + b))\n";
+        let report = judge_script("judge_accepted", adds, &config);
+        assert!(report.verdict == Verdict::Accepted);
+        assert!(report.results == vec![Verdict::Accepted, Verdict::Accepted]);
+
+        let first_only = "read a b\nif [ $a = 1 ]; then echo 3; else echo 0; fi\n";
+        let report = judge_script("judge_wrong_answer", first_only, &config);
+        assert!(report.verdict == Verdict::WrongAnswer);
+        assert!(report.results == vec![Verdict::Accepted, Verdict::WrongAnswer]);
+        assert!(report.details == "sample 2: expected \"42\", got \"0\"");
+
+        let report = judge_script("judge_time_limit", "sleep 2\n", &config);
+        assert!(report.results == vec![Verdict::TimeLimitExceeded; 2]);
+
+        let report = judge_script("judge_runtime_error", "echo oops >&2\nexit 3\n", &config);
+        assert!(report.verdict == Verdict::RuntimeError);
+        assert!(report.details == "sample 1: oops");
+
+        let report = judge_script("judge_compile_error", "if then fi (\n", &config);
+        assert!(report.verdict == Verdict::CompileError);
+        assert!(report.results.is_empty());
+    }
+
+    #[test]
+    #[serial]
+    fn import_samples_and_find_solution() {
+        let (db_path, cleanup) = create_temp_dir("import_samples_and_find_solution");
+        let dir = db_path.parent().unwrap();
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join("2.in"), "20 22\n").unwrap();
+        std::fs::write(dir.join("2.out"), "42\n").unwrap();
+        std::fs::write(dir.join("1.in"), "1 2\n").unwrap();
+        std::fs::write(dir.join("1.ans"), "3\n").unwrap();
+        // no answer, not a sample
+        std::fs::write(dir.join("3.in"), "5 5\n").unwrap();
+        let samples = import_samples(dir).unwrap();
+        assert!(
+            samples
+                == vec![
+                    ("1 2\n".to_string(), "3\n".to_string()),
+                    ("20 22\n".to_string(), "42\n".to_string())
+                ]
+        );
+
+        let languages = JudgeConfig::default().languages;
+        assert!(find_solution(dir, &languages).unwrap().is_none());
+        std::fs::write(dir.join("old.py"), "").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::write(dir.join("new.cpp"), "").unwrap();
+        assert!(find_solution(dir, &languages).unwrap() == Some(dir.join("new.cpp")));
+        assert!(cleanup().is_ok());
+    }
+}
@@ -1,23 +1,41 @@
 extern crate sm;
 use screens::ui;
-use sm::{sm, State};
+use sm::sm;
 
+mod cli;
+mod config;
 mod constants;
 mod database;
+mod diff;
+mod drip;
+mod editor;
 mod error;
+mod feeds;
+mod grading;
+mod judge;
+mod markdown;
+mod opener;
+mod planner;
+mod scheduling;
 mod screens;
+mod sources;
 mod types;
+mod urls;
 
+use clap::Parser;
+use cli::{Cli, Command, TagFilter};
+use config::AppConfig;
 use crossterm::{
+    cursor,
     event::{self, DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use error::AppResult;
+use error::{AppError, AppResult};
 use spaced_rs::UserReview;
 use std::{io, time::Duration};
 use tui::{backend::CrosstermBackend, Terminal};
-use types::{AppConfig, Term};
+use types::Term;
 
 // These are our screen states as well as our transitions between them
 sm! {
@@ -34,6 +52,10 @@ sm! {
             MainScreen => MainScreen
         }
 
+        SyncFeeds {
+            MainScreen => MainScreen
+        }
+
         StartReview {
             MainScreen => ReviewInfoScreen
         }
@@ -71,6 +93,67 @@ sm! {
         ReviewHard {
             ReviewEvalScreen => MainScreen
         }
+
+        UndoReview {
+            MainScreen => MainScreen
+        }
+
+        CycleOrder {
+            MainScreen => MainScreen
+        }
+
+        SetStatus {
+            ReviewInfoScreen => MainScreen
+        }
+
+        EditNotes {
+            ReviewInfoScreen => ReviewInfoScreen
+            ReviewEvalScreen => ReviewEvalScreen
+        }
+
+        EditSamples {
+            ReviewInfoScreen => ReviewInfoScreen
+        }
+
+        RunJudge {
+            ReviewTimerScreen => ReviewTimerScreen
+        }
+
+        AttachSolution {
+            ReviewEvalScreen => ReviewEvalScreen
+        }
+
+        ShowStats {
+            MainScreen => StatsScreen
+        }
+
+        CloseStats {
+            StatsScreen => MainScreen
+        }
+
+        ShowInbox {
+            MainScreen => InboxScreen
+        }
+
+        EditInbox {
+            InboxScreen => InboxScreen
+        }
+
+        CloseInbox {
+            InboxScreen => MainScreen
+        }
+
+        ShowSuspended {
+            MainScreen => SuspendedScreen
+        }
+
+        EditSuspended {
+            SuspendedScreen => SuspendedScreen
+        }
+
+        CloseSuspended {
+            SuspendedScreen => MainScreen
+        }
      }
 }
 
@@ -80,75 +163,155 @@ use crate::Screen::{
     *,
 };
 
-use crate::types::Item;
+use crate::opener::{CommandOpener, UrlOpener};
+use crate::types::{
+    InboxAction, InboxInput, InboxInputKind, InboxView, Item, ItemStatus, JudgeReport, ReviewLog,
+    ReviewUndo, SessionPlan, Solution, Stats, SuspendedView,
+};
+use rusqlite::Connection;
 use std::time::Instant;
 
 pub struct AppState {
     pub screen_state: ScreenState,
     pub should_quit: bool,
-    pub due_items: Option<Vec<String>>,
-    pub due_item: Option<String>,
+    pub conn: Connection,
+    pub opener: Box<dyn UrlOpener>,
+    // today's queue, reviews are taken from the back
+    pub due_items: Option<Vec<Item>>,
+    // what the queue adds up to and what we did today
+    pub plan: Option<SessionPlan>,
+    pub due_item: Option<Item>,
     pub n_due: Option<u32>,
     pub n_new: Option<u32>,
     pub total: Option<u32>,
     pub time_stamp: Option<Instant>,
     pub duration: Option<Duration>,
     pub review_result: Option<UserReview>,
+    // the grade the eval screen has selected, space confirms it
+    pub selected_grade: Option<UserReview>,
+    // the last run of the local judge on the item under review
+    pub judge_report: Option<JudgeReport>,
+    // the archived solutions of the item under review, oldest first
+    pub solutions: Vec<Solution>,
+    // the solution to archive with the review once it is graded
+    pub attached_solution: Option<Solution>,
+    // the reviews graded this session, newest last, so they can be taken back
+    pub undo: Vec<ReviewUndo>,
+    pub stats: Option<Stats>,
+    pub inbox: InboxView,
+    pub suspended: SuspendedView,
+    // the status the item under review gets, set by the key that asked for it
+    pub new_status: Option<ItemStatus>,
+    // a one line report of the last action, shown on the main and inbox screens
+    pub message: Option<String>,
+    // the session only reviews due items with these tags
+    pub tag_filter: TagFilter,
+    pub config: AppConfig,
 }
 
 impl AppState {
-    fn init(conf: AppConfig) -> Self {
+    fn init(conf: AppConfig) -> AppResult<Self> {
+        let conn = database::open_connection(&conf.db_path)?;
+        let opener = CommandOpener {
+            command: conf.opener_command.clone(),
+        };
+        Ok(Self::new(conn, Box::new(opener), conf))
+    }
+
+    fn new(conn: Connection, opener: Box<dyn UrlOpener>, config: AppConfig) -> Self {
         Self {
             screen_state: Machine::new(WelcomeScreen).as_enum(),
             should_quit: false,
+            conn,
+            opener,
             due_items: None,
+            plan: None,
+            due_item: None,
             n_due: None,
             n_new: None,
             total: None,
             time_stamp: None,
             duration: None,
             review_result: None,
+            selected_grade: None,
+            judge_report: None,
+            solutions: vec![],
+            attached_solution: None,
+            undo: vec![],
+            stats: None,
+            inbox: InboxView::default(),
+            suspended: SuspendedView::default(),
+            new_status: None,
+            message: None,
+            tag_filter: TagFilter::default(),
+            config,
         }
     }
 }
 
 fn main() -> AppResult<()> {
-    // setup terminal
+    let cli = Cli::parse();
+    let mut config = config::load_config()?;
+    if let Some(db_path) = cli.db {
+        config.db_path = db_path;
+    }
+
+    match cli.command {
+        None => run_tui(config, TagFilter::default()),
+        Some(Command::Review { filter }) => run_tui(config, filter),
+        Some(command) => {
+            let mut conn = database::open_connection(&config.db_path)?;
+            cli::run(command, &mut conn, &config, &mut io::stdout())
+        }
+    }
+}
+
+/// puts the terminal back the way we found it when dropped, so that errors and panics in the app
+/// don't leave the shell in raw mode on the alternate screen
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // we are on our way out, there is nothing left to do when this fails
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            cursor::Show
+        );
+    }
+}
+
+fn run_tui(config: AppConfig, tag_filter: TagFilter) -> AppResult<()> {
+    // setup terminal, it is restored when the guard goes out of scope
     enable_raw_mode()?;
+    let _guard = TerminalGuard;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let config = AppConfig::default();
-
-    run_app(&mut terminal, config)?;
-
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-
-    Ok(())
+    run_app(&mut terminal, config, tag_filter)
 }
 
 #[allow(unreachable_code)]
-fn run_app(term: &mut Term, conf: AppConfig) -> AppResult<()> {
-    let mut app_state = AppState::init(conf);
+fn run_app(term: &mut Term, conf: AppConfig, tag_filter: TagFilter) -> AppResult<()> {
+    let mut app_state = AppState::init(conf)?;
+    app_state.tag_filter = tag_filter;
 
     // initial render
     ui(term, &app_state)?;
 
     loop {
         // first we see wait on the next event and see if we should change screen
-        app_state = update_screen_state(app_state)?;
+        let (next_state, transitioned) = update_screen_state(app_state)?;
+        app_state = next_state;
 
         // then we perform any action associated with our current state+'how we got here' combo
-        app_state = perform_action(app_state);
+        if transitioned {
+            app_state = perform_action(term, app_state)?;
+        }
         if app_state.should_quit {
             break;
         }
@@ -160,122 +323,1349 @@ fn run_app(term: &mut Term, conf: AppConfig) -> AppResult<()> {
     Ok(())
 }
 
-fn perform_action(mut s: AppState) -> AppState {
+fn perform_action(term: &mut Term, mut s: AppState) -> AppResult<AppState> {
+    // messages only describe the transition that produced them
+    s.message = None;
     match &s.screen_state {
         GoodbyeScreenByQuit(_) => s.should_quit = true,
         // we do nothing
         InitialWelcomeScreen(_) => {}
         // we need to load in the due items from the database
         MainScreenByStartApp(_) => {
-            // we need to load in the items
-            // Mockitems
-            let mut items: Vec<String> = vec![
-                "https://open.kattis.com/problems/sequences".into(),
-                "https://open.kattis.com/problems/3dprinter".into(),
-                "https://open.kattis.com/problems/aa".into(),
-            ];
-            s.due_items = Some(items);
-        }
-        ReviewInfoScreenByStartReview(_) => {
-            // TODO, how should I handle the case were there are no more due items?
-            if s.due_item.is_none() {
-                // try pop from due_items
-                s.due_items
-                    .and_then(|list| list.pop())
-                    .and_then(|item| Some(item));
+            let promoted = drip_inbox(&mut s)?;
+            let moved = spread_backlog(&mut s)?;
+            refresh_main_screen(&mut s)?;
+            let mut reports = vec![];
+            if promoted > 0 {
+                reports.push(format!("promoted {promoted} problems from the inbox"));
+            }
+            if moved > 0 {
+                reports.push(format!("moved {moved} overdue reviews to the coming days"));
+            }
+            if !reports.is_empty() {
+                s.message = Some(reports.join(", "));
+            }
+        }
+        ReviewInfoScreenByStartReview(sm) => {
+            let sm = sm.clone();
+            start_review(&mut s)?;
+            // skip the info screen and go straight to solving
+            if s.config.timer.auto_start && s.due_item.is_some() {
+                s.screen_state = sm.transition(StartTimer).as_enum();
+                s.time_stamp = Some(Instant::now());
+                s.duration = None;
+            }
+        }
+        // the cancelled item is still due, so it ends up in the reloaded queue again
+        MainScreenByCancelReview(_) => {
+            s.time_stamp = None;
+            s.duration = None;
+            refresh_main_screen(&mut s)?
+        }
+        MainScreenByPromoteNewToDue(_) => {
+            if let Some(url_item) = database::get_n_urls_from_inbox(&s.conn, 1)?.pop() {
+                database::promote_inbox_item(&mut s.conn, url_item.id, &s.config.scheduling)?;
+            }
+            refresh_main_screen(&mut s)?;
+        }
+        MainScreenBySyncFeeds(_) => {
+            let now = database::current_timestamp();
+            let results = feeds::sync_feeds(&mut s.conn, now)?;
+            refresh_main_screen(&mut s)?;
+            s.message = Some(feeds::summarize(&results));
+        }
+        ReviewTimerScreenByStartTimer(_) | ReviewTimerScreenByRestartTimer(_) => {
+            s.time_stamp = Some(Instant::now());
+            s.duration = None;
+        }
+        ReviewInfoScreenByCancelTimer(_) => s.time_stamp = None,
+        ReviewTimerScreenByRunJudge(_) => run_judge(&mut s)?,
+        ReviewEvalScreenByAttachSolution(_) => attach_solution(&mut s),
+        ReviewEvalScreenByStopTimer(_) => {
+            s.duration = s.time_stamp.map(|t| t.elapsed());
+            s.selected_grade = s.due_item.as_ref().map(|item| {
+                let target = grading::target_time(item, &s.config.grading);
+                let verdict = s.judge_report.as_ref().map(|report| report.verdict);
+                grading::suggest_grade(s.duration, target, verdict)
+            });
+        }
+        MainScreenByReviewEasy(_) => grade_review(&mut s, UserReview::TooEasy)?,
+        MainScreenByReviewNormal(_) => grade_review(&mut s, UserReview::JustEnough)?,
+        MainScreenByReviewHard(_) => grade_review(&mut s, UserReview::TooHard)?,
+        MainScreenByUndoReview(_) => undo_review(&mut s)?,
+        MainScreenByCycleOrder(_) => {
+            s.config.queue.order = s.config.queue.order.next();
+            refresh_main_screen(&mut s)?;
+            s.message = Some(format!("queue order: {}", s.config.queue.order.name()));
+        }
+        MainScreenBySetStatus(_) => set_item_status(&mut s)?,
+        StatsScreenByShowStats(_) => {
+            let now = database::current_timestamp();
+            s.stats = Some(database::get_stats(&s.conn, now)?);
+        }
+        // reviews may have become due while we were looking at the stats
+        MainScreenByCloseStats(_) => refresh_main_screen(&mut s)?,
+        InboxScreenByShowInbox(_) => {
+            s.inbox = InboxView::default();
+            load_inbox(&mut s)?;
+        }
+        InboxScreenByEditInbox(_) => edit_inbox(&mut s)?,
+        MainScreenByCloseInbox(_) => refresh_main_screen(&mut s)?,
+        SuspendedScreenByShowSuspended(_) => {
+            s.suspended = SuspendedView::default();
+            load_suspended(&mut s)?;
+        }
+        SuspendedScreenByEditSuspended(_) => edit_suspended(&mut s)?,
+        MainScreenByCloseSuspended(_) => refresh_main_screen(&mut s)?,
+        ReviewInfoScreenByEditNotes(_) | ReviewEvalScreenByEditNotes(_) => {
+            if let Some(item) = s.due_item.as_mut() {
+                let file_name = format!("spaced-programming-notes-{}.md", item.id);
+                let notes = editor::edit_in_editor(term, &file_name, &item.notes)?;
+                let now = database::current_timestamp();
+                database::update_item_notes(&mut s.conn, item.id, &notes, now)?;
+                item.notes = notes;
+            }
+        }
+        ReviewInfoScreenByEditSamples(_) => {
+            if let Some(item) = s.due_item.as_ref() {
+                let file_name = format!("spaced-programming-samples-{}.txt", item.id);
+                let samples = database::get_sample_tests(&s.conn, item.id)?;
+                let text = judge::samples_to_text(&samples);
+                let text = editor::edit_in_editor(term, &file_name, &text)?;
+                let samples = judge::samples_from_text(&text);
+                database::replace_sample_tests(&mut s.conn, item.id, &samples)?;
+                s.message = Some(format!("{} sample tests", samples.len()));
+            }
+        }
+    }
+    Ok(s)
+}
+
+/// promotes the day's share of inbox entries, if the drip is on and has not run today yet.
+/// Returns how many entries were promoted.
+fn drip_inbox(s: &mut AppState) -> AppResult<usize> {
+    let config = &s.config.drip;
+    let now = database::current_timestamp();
+    if config.items_per_day == 0 || database::dripped_on(&s.conn, database::day_of(now))? {
+        return Ok(0);
+    }
+    let n_inbox = database::count_inbox_items(&s.conn)? as usize;
+    let entries = database::get_n_urls_from_inbox(&s.conn, n_inbox)?;
+    let ids = drip::pick_entries(&entries, config.items_per_day as usize, config.balance);
+    let promoted = database::drip_inbox(&mut s.conn, &ids, now, &s.config.scheduling)?;
+    Ok(promoted.map_or(0, |items| items.len()))
+}
+
+/// moves the overdue reviews of the session's tags that do not fit into today to the coming days,
+/// if spreading the backlog is turned on and we did not spread it today yet. Returns how many
+/// reviews were moved.
+fn spread_backlog(s: &mut AppState) -> AppResult<usize> {
+    let now = database::current_timestamp();
+    if database::backlog_spread_on(&s.conn, database::day_of(now))? {
+        return Ok(0);
+    }
+    let done = database::done_today(&s.conn, now)?;
+    let due = s.tag_filter.due_items(&s.conn, now)?;
+    let moved = planner::spread_backlog(due, &done, &s.config, now);
+    let n_moved = moved.len();
+    database::update_items(&mut s.conn, moved)?;
+    // spreading again today would find nothing left to move, so failing in between does no harm
+    database::mark_backlog_spread(&s.conn, now)?;
+    Ok(n_moved)
+}
+
+/// takes the next item from the due queue and opens its problem in the browser
+fn start_review(s: &mut AppState) -> AppResult<()> {
+    // the main screen only lets us start a review while there are due items
+    if s.due_item.is_none() {
+        // try pop from due_items
+        s.due_item = s.due_items.as_mut().and_then(|list| list.pop());
+        if let Some(item) = s.due_item.as_mut() {
+            // the page may have been saved to the cache since we last looked
+            let metadata =
+                sources::problem_metadata_with_cache(&item.url, &s.config.page_cache_dir);
+            if metadata != item.metadata {
+                database::set_item_metadata(&s.conn, item.id, metadata.as_ref())?;
+                item.metadata = metadata;
             }
+            s.solutions = database::get_item_solutions(&s.conn, item.id)?;
+            // the review goes on without the browser, the message shows which url to open
+            if let Err(err) = s.opener.open(&item.url) {
+                s.message = Some(format!("could not open {}: {err}", item.url));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// reschedules the item under review according to {review} and goes back to the main screen
+fn grade_review(s: &mut AppState, review: UserReview) -> AppResult<()> {
+    if let Some(item) = s.due_item.take() {
+        let now = database::current_timestamp();
+        let before = database::get_item(&s.conn, item.id)?;
+        let (interval_before, difficulty_before) = (
+            item.scheduling_data.interval,
+            item.scheduling_data.difficulty,
+        );
+        let algorithm = s.config.scheduling.algorithm_for(&item.tags);
+        let scheduler = scheduling::scheduler(algorithm);
+        let item = scheduling::review_item(item, &review, now, scheduler);
+        let item = balance_due(&s.conn, item, now, &s.config.scheduling)?;
+        let log = ReviewLog {
+            id: 0,
+            item_id: item.id,
+            timestamp: now,
+            grade: scheduling::copy_review(&review),
+            duration: s.duration,
+            interval_before,
+            interval_after: item.scheduling_data.interval,
+            difficulty_before,
+            difficulty_after: item.scheduling_data.difficulty,
+            verdict: s.judge_report.as_ref().map(|report| report.verdict),
+        };
+        let review_id = database::record_review(&mut s.conn, item, &log)?;
+        s.undo.push(ReviewUndo {
+            item: before,
+            review_id,
+        });
+        if let Some(mut solution) = s.attached_solution.take() {
+            solution.review_id = Some(review_id);
+            database::add_solution(&s.conn, &solution)?;
         }
-        MainScreenByCancelReview(_) => todo!(),
-        MainScreenByPromoteNewToDue(_) => todo!(),
-        ReviewTimerScreenByStartTimer(_) => todo!(),
-        ReviewTimerScreenByRestartTimer(_) => todo!(),
-        ReviewInfoScreenByCancelTimer(_) => todo!(),
-        ReviewEvalScreenByStopTimer(_) => todo!(),
-        MainScreenByReviewEasy(_) => todo!(),
-        MainScreenByReviewNormal(_) => todo!(),
-        MainScreenByReviewHard(_) => todo!(),
     }
-    s
+    s.review_result = Some(review);
+    refresh_main_screen(s)
 }
 
-// TODO, Here I have to
-fn update_screen_state(mut app_state: AppState) -> AppResult<AppState> {
+/// moves the due date of the {item} we just reviewed to the least busy day its fuzz allows
+fn balance_due(
+    conn: &Connection,
+    mut item: Item,
+    now: u64,
+    config: &config::SchedulingConfig,
+) -> AppResult<Item> {
+    let fuzz = scheduling::fuzz_days(
+        item.scheduling_data.interval,
+        config.fuzz,
+        config.max_fuzz_days,
+    );
+    if fuzz > 0 {
+        let today = database::day_of(now);
+        let n_days = (database::day_of(item.due) + fuzz - today + 1) as usize;
+        let load = database::due_forecast(conn, now, n_days)?;
+        item.due = scheduling::balance_due(item.due, fuzz, today, &load);
+    }
+    Ok(item)
+}
+
+/// takes back the last review we graded and puts its item at the front of the due queue, so it
+/// is the next one we review
+fn undo_review(s: &mut AppState) -> AppResult<()> {
+    let Some(undo) = s.undo.pop() else {
+        s.message = Some("no review to undo".into());
+        return Ok(());
+    };
+    let (id, url) = (undo.item.id, undo.item.url.clone());
+    database::undo_review(&mut s.conn, undo.item, undo.review_id)?;
+    s.review_result = None;
+    refresh_main_screen(s)?;
+    // reviews are taken from the back of the queue
+    if let Some(items) = s.due_items.as_mut() {
+        if let Some(index) = items.iter().position(|item| item.id == id) {
+            let item = items.remove(index);
+            items.push(item);
+        }
+    }
+    s.message = Some(format!("undid the review of {url}"));
+    Ok(())
+}
+
+/// leaves the item under review out of reviews, for today or until we make it active again
+fn set_item_status(s: &mut AppState) -> AppResult<()> {
+    if let (Some(item), Some(status)) = (s.due_item.as_ref(), s.new_status.take()) {
+        database::set_item_status(&s.conn, item.id, status)?;
+        let message = format!("{} {}", status.name(), item.url);
+        refresh_main_screen(s)?;
+        s.message = Some(message);
+    }
+    Ok(())
+}
+
+/// judges the newest solution in the solution directory against the samples of the item under
+/// review. Problems with the setup end up in the message rather than ending the review.
+fn run_judge(s: &mut AppState) -> AppResult<()> {
+    let Some(item) = s.due_item.as_ref() else {
+        return Ok(());
+    };
+    let samples = database::get_sample_tests(&s.conn, item.id)?;
+    let config = &s.config.judge;
+    let solution =
+        judge::find_solution(&config.solution_dir, &config.languages).map_err(AppError::JudgeError);
+    match solution {
+        _ if samples.is_empty() => s.message = Some("no sample tests for this problem".into()),
+        Ok(None) => {
+            s.message = Some(format!("no solution in {}", config.solution_dir.display()));
+        }
+        Ok(Some(solution)) => match judge::judge(&solution, &samples, config) {
+            Ok(report) => s.judge_report = Some(report),
+            Err(err) => s.message = Some(err.to_string()),
+        },
+        Err(err) => s.message = Some(err.to_string()),
+    }
+    Ok(())
+}
+
+/// reads the solution we just judged, or else the newest one in the solution directory, to archive
+/// it with the review
+fn attach_solution(s: &mut AppState) {
+    let Some(item) = s.due_item.as_ref() else {
+        return;
+    };
+    let config = &s.config.judge;
+    let path = match &s.judge_report {
+        Some(report) => Ok(Some(report.solution.clone())),
+        None => judge::find_solution(&config.solution_dir, &config.languages)
+            .map_err(AppError::JudgeError),
+    };
+    // like the judge, problems with the solution end up in the message rather than ending the review
+    let path = match path {
+        Ok(Some(path)) => path,
+        Ok(None) => {
+            s.message = Some(format!("no solution in {}", config.solution_dir.display()));
+            return;
+        }
+        Err(err) => {
+            s.message = Some(err.to_string());
+            return;
+        }
+    };
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) => {
+            s.message = Some(format!("could not read {}: {err}", path.display()));
+            return;
+        }
+    };
+    let language = path.extension().unwrap_or_default().to_string_lossy();
+    s.attached_solution = Some(Solution {
+        id: 0,
+        item_id: item.id,
+        review_id: None,
+        language: language.into_owned(),
+        source,
+        timestamp: database::current_timestamp(),
+    });
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    s.message = Some(format!("{file_name} is saved with the review"));
+}
+
+/// plans today's queue and reloads the counts shown on the main screen from the database. Whatever
+/// item we were reviewing is done with at this point.
+fn refresh_main_screen(s: &mut AppState) -> AppResult<()> {
+    let now = database::current_timestamp();
+    s.due_item = None;
+    s.judge_report = None;
+    s.selected_grade = None;
+    s.solutions.clear();
+    s.attached_solution = None;
+    let done = database::done_today(&s.conn, now)?;
+    let averages = database::average_solve_times(&s.conn)?;
+    let overall = database::average_solve_time(&s.conn)?;
+    let grading = &s.config.grading;
+    let due = s.tag_filter.due_items(&s.conn, now)?;
+    s.n_due = Some(due.len() as u32);
+    let (queue, plan) = planner::plan_session(due, &done, &s.config, now, |item| {
+        planner::estimate(item, &averages, overall, grading)
+    });
+    s.due_items = Some(queue.into_iter().rev().collect());
+    s.plan = Some(plan);
+    s.n_new = Some(database::count_inbox_items(&s.conn)?);
+    s.total = Some(database::count_items(&s.conn)?);
+    Ok(())
+}
+
+/// reloads the inbox screen's entries, keeping the selection within bounds
+fn load_inbox(s: &mut AppState) -> AppResult<()> {
+    let n_inbox = database::count_inbox_items(&s.conn)? as usize;
+    s.inbox.entries = database::get_n_urls_from_inbox(&s.conn, n_inbox)?;
+    s.inbox.selected = s.inbox.selected.min(n_inbox.saturating_sub(1));
+    Ok(())
+}
+
+/// applies the change asked for on the inbox screen and reloads it
+fn edit_inbox(s: &mut AppState) -> AppResult<()> {
+    match s.inbox.action.take() {
+        Some(InboxAction::Move(id, position)) => {
+            database::move_inbox_item(&mut s.conn, id, position)?
+        }
+        Some(InboxAction::Delete(id)) => database::remove_new_item(&s.conn, id)?,
+        Some(InboxAction::Promote(id)) => {
+            database::promote_inbox_item(&mut s.conn, id, &s.config.scheduling)?;
+        }
+        Some(InboxAction::AddUrls(text)) => {
+            let urls = text.split_whitespace().map(String::from).collect();
+            let report = database::add_urls_to_inbox(&s.conn, urls)?;
+            s.message = Some(format!(
+                "added {}, {} already in the inbox, {} already items",
+                report.added.len(),
+                report.already_in_inbox.len(),
+                report.already_items.len()
+            ));
+        }
+        Some(InboxAction::EditTags(id, text)) => {
+            let tags: Vec<&str> = text.split([',', ' ']).filter(|t| !t.is_empty()).collect();
+            let (removed, added): (Vec<&str>, Vec<&str>) =
+                tags.into_iter().partition(|tag| tag.starts_with('-'));
+            database::add_inbox_tags(&s.conn, id, &added)?;
+            for tag in removed {
+                database::remove_inbox_tag(&s.conn, id, &tag[1..])?;
+            }
+        }
+        None => {}
+    }
+    load_inbox(s)
+}
+
+/// reloads the suspended items screen, keeping the selection within bounds
+fn load_suspended(s: &mut AppState) -> AppResult<()> {
+    let now = database::current_timestamp();
+    s.suspended.items = database::get_suspended_items(&s.conn, now)?;
+    s.suspended.selected = s
+        .suspended
+        .selected
+        .min(s.suspended.items.len().saturating_sub(1));
+    Ok(())
+}
+
+/// gives the item selected on the suspended items screen the status asked for and reloads it
+fn edit_suspended(s: &mut AppState) -> AppResult<()> {
+    if let Some((id, status)) = s.suspended.action.take() {
+        database::set_item_status(&s.conn, id, status)?;
+    }
+    load_suspended(s)
+}
+
+/// what a key press on the main screen asks for
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MainAction {
+    StartReview,
+    PromoteNew,
+    ShowStats,
+    SyncFeeds,
+    ShowInbox,
+    UndoReview,
+    CycleOrder,
+    ShowSuspended,
+    Quit,
+}
+
+/// the action the key {code} of the main screen asks for, if any. Reviews only start while
+/// there are due items.
+fn main_key(keys: &config::KeyBindings, code: event::KeyCode, has_due: bool) -> Option<MainAction> {
+    use event::KeyCode::*;
+    match code {
+        Char(c) if (c == keys.review || c == ' ') && has_due => Some(MainAction::StartReview),
+        Char(c) if c == keys.promote_new => Some(MainAction::PromoteNew),
+        Char(c) if c == keys.stats => Some(MainAction::ShowStats),
+        Char(c) if c == keys.sync_feeds => Some(MainAction::SyncFeeds),
+        Char(c) if c == keys.inbox => Some(MainAction::ShowInbox),
+        Char(c) if c == keys.undo_review => Some(MainAction::UndoReview),
+        Char(c) if c == keys.queue_order => Some(MainAction::CycleOrder),
+        Char(c) if c == keys.suspended => Some(MainAction::ShowSuspended),
+        Esc => Some(MainAction::Quit),
+        _ => None,
+    }
+}
+
+/// the transition that carries out {action}, for the main screen reached through any event {E}
+fn main_transition<E: sm::Event>(sm: Machine<MainScreen, E>, action: MainAction) -> ScreenState {
+    match action {
+        MainAction::StartReview => sm.transition(StartReview).as_enum(),
+        MainAction::PromoteNew => sm.transition(PromoteNewToDue).as_enum(),
+        MainAction::ShowStats => sm.transition(ShowStats).as_enum(),
+        MainAction::SyncFeeds => sm.transition(SyncFeeds).as_enum(),
+        MainAction::ShowInbox => sm.transition(ShowInbox).as_enum(),
+        MainAction::UndoReview => sm.transition(UndoReview).as_enum(),
+        MainAction::CycleOrder => sm.transition(CycleOrder).as_enum(),
+        MainAction::ShowSuspended => sm.transition(ShowSuspended).as_enum(),
+        MainAction::Quit => sm.transition(Quit).as_enum(),
+    }
+}
+
+/// the status the key {c} of the review info screen gives the item under review, if any. Buried
+/// items come back at the start of tomorrow.
+fn status_key(keys: &config::KeyBindings, c: char) -> Option<ItemStatus> {
+    match c {
+        c if c == keys.bury => {
+            let tomorrow = database::day_of(database::current_timestamp()) + 1;
+            Some(ItemStatus::Buried(tomorrow * scheduling::SECONDS_PER_DAY))
+        }
+        c if c == keys.suspend => Some(ItemStatus::Suspended),
+        c if c == keys.retire => Some(ItemStatus::Retired),
+        _ => None,
+    }
+}
+
+/// moves the selection of the eval screen one grade towards hard or easy
+fn select_grade(selected_grade: &mut Option<UserReview>, harder: bool) {
+    let selected = selected_grade.as_ref().unwrap_or(&UserReview::JustEnough);
+    *selected_grade = Some(grading::shift_grade(selected, harder));
+}
+
+/// what a key press on a list screen (the inbox or the suspended items) amounts to
+enum ListKey {
+    // the key only changed the selection or the input line
+    Ignored,
+    // the key asked for a change, see InboxView::action and SuspendedView::action
+    Edit,
+    Close,
+}
+
+/// handles a key press on the inbox screen. While the input line is open every key goes to it.
+fn inbox_key(inbox: &mut InboxView, code: event::KeyCode) -> ListKey {
+    use event::KeyCode::*;
+    if let Some(input) = inbox.input.as_mut() {
+        match code {
+            Char(c) => input.text.push(c),
+            Backspace => {
+                input.text.pop();
+            }
+            Esc => inbox.input = None,
+            Enter if !input.text.trim().is_empty() => {
+                let text = std::mem::take(&mut input.text);
+                inbox.action = match input.kind {
+                    // stays open, a pasted list of urls arrives one line at a time
+                    InboxInputKind::Urls => Some(InboxAction::AddUrls(text)),
+                    InboxInputKind::Tags => {
+                        let id = inbox.entries.get(inbox.selected).map(|entry| entry.id);
+                        inbox.input = None;
+                        id.map(|id| InboxAction::EditTags(id, text))
+                    }
+                };
+                return match inbox.action {
+                    Some(_) => ListKey::Edit,
+                    None => ListKey::Ignored,
+                };
+            }
+            _ => {}
+        }
+        return ListKey::Ignored;
+    }
+    let selected = inbox.entries.get(inbox.selected).map(|entry| entry.id);
+    let action = match (code, selected) {
+        (Up | Char('k'), _) => {
+            inbox.selected = inbox.selected.saturating_sub(1);
+            None
+        }
+        (Down | Char('j'), _) => {
+            if inbox.selected + 1 < inbox.entries.len() {
+                inbox.selected += 1;
+            }
+            None
+        }
+        // the selection follows the moved entry
+        (Char('K'), Some(id)) if inbox.selected > 0 => {
+            inbox.selected -= 1;
+            Some(InboxAction::Move(id, inbox.selected))
+        }
+        (Char('J'), Some(id)) if inbox.selected + 1 < inbox.entries.len() => {
+            inbox.selected += 1;
+            Some(InboxAction::Move(id, inbox.selected))
+        }
+        (Char('g'), Some(id)) => {
+            inbox.selected = 0;
+            Some(InboxAction::Move(id, 0))
+        }
+        (Char('G'), Some(id)) => {
+            inbox.selected = inbox.entries.len() - 1;
+            Some(InboxAction::Move(id, inbox.selected))
+        }
+        (Char('d'), Some(id)) => Some(InboxAction::Delete(id)),
+        (Char('p'), Some(id)) => Some(InboxAction::Promote(id)),
+        (Char('t'), Some(_)) => {
+            inbox.input = Some(InboxInput {
+                kind: InboxInputKind::Tags,
+                text: String::new(),
+            });
+            None
+        }
+        (Char('a'), _) => {
+            inbox.input = Some(InboxInput {
+                kind: InboxInputKind::Urls,
+                text: String::new(),
+            });
+            None
+        }
+        (Esc, _) => return ListKey::Close,
+        _ => None,
+    };
+    inbox.action = action;
+    match inbox.action {
+        Some(_) => ListKey::Edit,
+        None => ListKey::Ignored,
+    }
+}
+
+/// handles a key press on the suspended items screen
+fn suspended_key(
+    view: &mut SuspendedView,
+    keys: &config::KeyBindings,
+    code: event::KeyCode,
+) -> ListKey {
+    use event::KeyCode::*;
+    let selected = view.items.get(view.selected).map(|item| item.id);
+    // the configured keys come first, so they win over the fixed ones for moving the selection
+    view.action = match (code, selected) {
+        (Char(c), Some(id)) if c == keys.unsuspend => Some((id, ItemStatus::Active)),
+        (Char(c), Some(id)) if c == keys.suspend => Some((id, ItemStatus::Suspended)),
+        (Char(c), Some(id)) if c == keys.retire => Some((id, ItemStatus::Retired)),
+        (Enter, Some(id)) => Some((id, ItemStatus::Active)),
+        (Up | Char('k'), _) => {
+            view.selected = view.selected.saturating_sub(1);
+            None
+        }
+        (Down | Char('j'), _) => {
+            if view.selected + 1 < view.items.len() {
+                view.selected += 1;
+            }
+            None
+        }
+        (Esc, _) => return ListKey::Close,
+        _ => None,
+    };
+    match view.action {
+        Some(_) => ListKey::Edit,
+        None => ListKey::Ignored,
+    }
+}
+
+/// waits for the next event and transitions the screen state accordingly. Also returns whether a
+/// transition happened, events without a binding should not trigger the action of the state again.
+fn update_screen_state(mut app_state: AppState) -> AppResult<(AppState, bool)> {
     use event::Event::*;
     use event::KeyCode::*;
+    // wake up every second without an event so the timer screen can redraw the elapsed time
+    if !event::poll(Duration::from_secs(1))? {
+        return Ok((app_state, false));
+    }
     let state_clone = app_state.screen_state.clone();
-    app_state.screen_state = match (app_state.screen_state, event::read()?) {
+    let has_due = app_state
+        .due_items
+        .as_ref()
+        .is_some_and(|items| !items.is_empty());
+    let keys = app_state.config.key_bindings.clone();
+    let next_state = match (app_state.screen_state, event::read()?) {
         (InitialWelcomeScreen(sm), Key(key)) => match key.code {
-            Esc => sm.transition(Quit).as_enum(),
-            _ => sm.transition(StartApp).as_enum(),
-        },
-        // main screen bindings
-        (MainScreenByStartApp(sm), Key(key)) => match key.code {
-            Char('r') | Char(' ') => sm.transition(StartReview).as_enum(),
-            Char('n') => sm.transition(PromoteNewToDue).as_enum(),
-            Esc => sm.transition(Quit).as_enum(),
-            _ => state_clone,
+            Esc => Some(sm.transition(Quit).as_enum()),
+            _ => Some(sm.transition(StartApp).as_enum()),
         },
-        (MainScreenByPromoteNewToDue(sm), Key(key)) => match key.code {
-            Char('r') | Char(' ') => sm.transition(StartReview).as_enum(),
-            Char('n') => sm.transition(PromoteNewToDue).as_enum(),
-            Esc => sm.transition(Quit).as_enum(),
-            _ => state_clone,
-        },
-
-        (MainScreenByReviewEasy(sm), Key(key)) => match key.code {
-            Char('r') | Char(' ') => sm.transition(StartReview).as_enum(),
-            Char('n') => sm.transition(PromoteNewToDue).as_enum(),
-            Esc => sm.transition(Quit).as_enum(),
-            _ => state_clone,
-        },
-
-        (MainScreenByReviewNormal(sm), Key(key)) => match key.code {
-            Char('r') | Char(' ') => sm.transition(StartReview).as_enum(),
-            Char('n') => sm.transition(PromoteNewToDue).as_enum(),
-            Esc => sm.transition(Quit).as_enum(),
-            _ => state_clone,
+        // main screen bindings, the same whichever transition led to the screen
+        (MainScreenByStartApp(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByPromoteNewToDue(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenBySyncFeeds(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByCloseInbox(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByReviewEasy(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByReviewNormal(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByReviewHard(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByCancelReview(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByUndoReview(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenBySetStatus(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByCloseSuspended(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByCycleOrder(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        (MainScreenByCloseStats(sm), Key(key)) => {
+            main_key(&keys, key.code, has_due).map(|action| main_transition(sm, action))
+        }
+        // stats screen bindings
+        (StatsScreenByShowStats(sm), Key(key)) => match key.code {
+            Char(c) if c == keys.stats || c == 'q' => Some(sm.transition(CloseStats).as_enum()),
+            Esc => Some(sm.transition(CloseStats).as_enum()),
+            _ => None,
         },
-        (MainScreenByReviewHard(sm), Key(key)) => match key.code {
-            Char('r') | Char(' ') => sm.transition(StartReview).as_enum(),
-            Char('n') => sm.transition(PromoteNewToDue).as_enum(),
-            Esc => sm.transition(Quit).as_enum(),
-            _ => state_clone,
+        // inbox screen bindings
+        (InboxScreenByShowInbox(sm), Key(key)) => match inbox_key(&mut app_state.inbox, key.code) {
+            ListKey::Edit => Some(sm.transition(EditInbox).as_enum()),
+            ListKey::Close => Some(sm.transition(CloseInbox).as_enum()),
+            ListKey::Ignored => None,
         },
-        (MainScreenByCancelReview(sm), Key(key)) => match key.code {
-            Char('r') | Char(' ') => sm.transition(StartReview).as_enum(),
-            Char('n') => sm.transition(PromoteNewToDue).as_enum(),
-            Esc => sm.transition(Quit).as_enum(),
-            _ => state_clone,
+        (InboxScreenByEditInbox(sm), Key(key)) => match inbox_key(&mut app_state.inbox, key.code) {
+            ListKey::Edit => Some(sm.transition(EditInbox).as_enum()),
+            ListKey::Close => Some(sm.transition(CloseInbox).as_enum()),
+            ListKey::Ignored => None,
         },
+        // suspended items screen bindings
+        (SuspendedScreenByShowSuspended(sm), Key(key)) => {
+            match suspended_key(&mut app_state.suspended, &keys, key.code) {
+                ListKey::Edit => Some(sm.transition(EditSuspended).as_enum()),
+                ListKey::Close => Some(sm.transition(CloseSuspended).as_enum()),
+                ListKey::Ignored => None,
+            }
+        }
+        (SuspendedScreenByEditSuspended(sm), Key(key)) => {
+            match suspended_key(&mut app_state.suspended, &keys, key.code) {
+                ListKey::Edit => Some(sm.transition(EditSuspended).as_enum()),
+                ListKey::Close => Some(sm.transition(CloseSuspended).as_enum()),
+                ListKey::Ignored => None,
+            }
+        }
         // Review screen bindings
         (ReviewInfoScreenByCancelTimer(sm), Key(key)) => match key.code {
-            Char(' ') => sm.transition(StartTimer).as_enum(),
-            Esc => sm.transition(CancelReview).as_enum(),
-            _ => state_clone,
+            Char(' ') => Some(sm.transition(StartTimer).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.edit_samples => Some(sm.transition(EditSamples).as_enum()),
+            Char(c) if status_key(&keys, c).is_some() => {
+                app_state.new_status = status_key(&keys, c);
+                Some(sm.transition(SetStatus).as_enum())
+            }
+            Esc => Some(sm.transition(CancelReview).as_enum()),
+            _ => None,
         },
         (ReviewInfoScreenByStartReview(sm), Key(key)) => match key.code {
-            Char(' ') => sm.transition(StartTimer).as_enum(),
-            Esc => sm.transition(CancelReview).as_enum(),
-            _ => state_clone,
+            Char(' ') => Some(sm.transition(StartTimer).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.edit_samples => Some(sm.transition(EditSamples).as_enum()),
+            Char(c) if status_key(&keys, c).is_some() => {
+                app_state.new_status = status_key(&keys, c);
+                Some(sm.transition(SetStatus).as_enum())
+            }
+            Esc => Some(sm.transition(CancelReview).as_enum()),
+            _ => None,
+        },
+        (ReviewInfoScreenByEditNotes(sm), Key(key)) => match key.code {
+            Char(' ') => Some(sm.transition(StartTimer).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.edit_samples => Some(sm.transition(EditSamples).as_enum()),
+            Char(c) if status_key(&keys, c).is_some() => {
+                app_state.new_status = status_key(&keys, c);
+                Some(sm.transition(SetStatus).as_enum())
+            }
+            Esc => Some(sm.transition(CancelReview).as_enum()),
+            _ => None,
+        },
+        (ReviewInfoScreenByEditSamples(sm), Key(key)) => match key.code {
+            Char(' ') => Some(sm.transition(StartTimer).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.edit_samples => Some(sm.transition(EditSamples).as_enum()),
+            Char(c) if status_key(&keys, c).is_some() => {
+                app_state.new_status = status_key(&keys, c);
+                Some(sm.transition(SetStatus).as_enum())
+            }
+            Esc => Some(sm.transition(CancelReview).as_enum()),
+            _ => None,
         },
         (ReviewTimerScreenByStartTimer(sm), Key(key)) => match key.code {
-            Char(' ') => sm.transition(StopTimer).as_enum(),
-            Esc => sm.transition(CancelTimer).as_enum(),
-            _ => state_clone,
+            Char(' ') => Some(sm.transition(StopTimer).as_enum()),
+            Char(c) if c == keys.run_judge => Some(sm.transition(RunJudge).as_enum()),
+            Esc => Some(sm.transition(CancelTimer).as_enum()),
+            _ => None,
         },
         (ReviewTimerScreenByRestartTimer(sm), Key(key)) => match key.code {
-            Char(' ') => sm.transition(StopTimer).as_enum(),
-            Esc => sm.transition(CancelTimer).as_enum(),
-            _ => state_clone,
+            Char(' ') => Some(sm.transition(StopTimer).as_enum()),
+            Char(c) if c == keys.run_judge => Some(sm.transition(RunJudge).as_enum()),
+            Esc => Some(sm.transition(CancelTimer).as_enum()),
+            _ => None,
+        },
+        (ReviewTimerScreenByRunJudge(sm), Key(key)) => match key.code {
+            Char(' ') => Some(sm.transition(StopTimer).as_enum()),
+            Char(c) if c == keys.run_judge => Some(sm.transition(RunJudge).as_enum()),
+            Esc => Some(sm.transition(CancelTimer).as_enum()),
+            _ => None,
         },
         (ReviewEvalScreenByStopTimer(sm), Key(key)) => match key.code {
-            Char(' ') | Char('2') => sm.transition(ReviewNormal).as_enum(),
-            Char('1') => sm.transition(ReviewEasy).as_enum(),
-            Char('3') => sm.transition(ReviewHard).as_enum(),
-            Esc => sm.transition(RestartTimer).as_enum(),
-            _ => state_clone,
+            Char(' ') => match app_state.selected_grade {
+                Some(UserReview::TooEasy) => Some(sm.transition(ReviewEasy).as_enum()),
+                Some(UserReview::TooHard) => Some(sm.transition(ReviewHard).as_enum()),
+                _ => Some(sm.transition(ReviewNormal).as_enum()),
+            },
+            Left | Right => {
+                select_grade(&mut app_state.selected_grade, key.code == Right);
+                None
+            }
+            Char(c) if c == keys.normal => Some(sm.transition(ReviewNormal).as_enum()),
+            Char(c) if c == keys.easy => Some(sm.transition(ReviewEasy).as_enum()),
+            Char(c) if c == keys.hard => Some(sm.transition(ReviewHard).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.attach_solution => Some(sm.transition(AttachSolution).as_enum()),
+            Esc => Some(sm.transition(RestartTimer).as_enum()),
+            _ => None,
         },
-        _ => state_clone,
+        (ReviewEvalScreenByAttachSolution(sm), Key(key)) => match key.code {
+            Char(' ') => match app_state.selected_grade {
+                Some(UserReview::TooEasy) => Some(sm.transition(ReviewEasy).as_enum()),
+                Some(UserReview::TooHard) => Some(sm.transition(ReviewHard).as_enum()),
+                _ => Some(sm.transition(ReviewNormal).as_enum()),
+            },
+            Left | Right => {
+                select_grade(&mut app_state.selected_grade, key.code == Right);
+                None
+            }
+            Char(c) if c == keys.normal => Some(sm.transition(ReviewNormal).as_enum()),
+            Char(c) if c == keys.easy => Some(sm.transition(ReviewEasy).as_enum()),
+            Char(c) if c == keys.hard => Some(sm.transition(ReviewHard).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.attach_solution => Some(sm.transition(AttachSolution).as_enum()),
+            Esc => Some(sm.transition(RestartTimer).as_enum()),
+            _ => None,
+        },
+        (ReviewEvalScreenByEditNotes(sm), Key(key)) => match key.code {
+            Char(' ') => match app_state.selected_grade {
+                Some(UserReview::TooEasy) => Some(sm.transition(ReviewEasy).as_enum()),
+                Some(UserReview::TooHard) => Some(sm.transition(ReviewHard).as_enum()),
+                _ => Some(sm.transition(ReviewNormal).as_enum()),
+            },
+            Left | Right => {
+                select_grade(&mut app_state.selected_grade, key.code == Right);
+                None
+            }
+            Char(c) if c == keys.normal => Some(sm.transition(ReviewNormal).as_enum()),
+            Char(c) if c == keys.easy => Some(sm.transition(ReviewEasy).as_enum()),
+            Char(c) if c == keys.hard => Some(sm.transition(ReviewHard).as_enum()),
+            Char(c) if c == keys.edit_notes => Some(sm.transition(EditNotes).as_enum()),
+            Char(c) if c == keys.attach_solution => Some(sm.transition(AttachSolution).as_enum()),
+            Esc => Some(sm.transition(RestartTimer).as_enum()),
+            _ => None,
+        },
+        _ => None,
     };
-    Ok(app_state)
+    let transitioned = next_state.is_some();
+    app_state.screen_state = next_state.unwrap_or(state_clone);
+    Ok((app_state, transitioned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::{create_temp_dir, new_item};
+    use crate::opener::RecordingOpener;
+    use crate::scheduling::SECONDS_PER_DAY;
+    use crate::types::SchedulingData;
+    use serial_test::serial;
+
+    /// util function that creates an app state on a database with one due item
+    fn app_with_due_item(conn: Connection, opener: &RecordingOpener) -> AppState {
+        let item = new_item("https://open.kattis.com/problems/hello", 10);
+        database::insert_item(&conn, item).unwrap();
+        let mut s = AppState::new(conn, Box::new(opener.clone()), AppConfig::default());
+        refresh_main_screen(&mut s).unwrap();
+        s
+    }
+
+    #[test]
+    #[serial]
+    fn start_review_opens_problem() {
+        let (db_path, cleanup) = create_temp_dir("start_review_opens_problem");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        assert!(s.n_due == Some(1));
+
+        assert!(start_review(&mut s).is_ok());
+        assert!(s.due_item.is_some());
+        // metadata is filled in for items from known sites
+        let id = s.due_item.as_ref().unwrap().id;
+        let metadata = database::get_item(&s.conn, id).unwrap().metadata.unwrap();
+        assert!(metadata.site == "kattis" && metadata.problem_id == "hello");
+        assert!(*opener.opened.borrow() == vec!["https://open.kattis.com/problems/hello"]);
+        // starting again while the item is under review does not open it twice
+        assert!(start_review(&mut s).is_ok());
+        assert!(opener.opened.borrow().len() == 1);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn start_review_without_opener() {
+        let (db_path, cleanup) = create_temp_dir("start_review_without_opener");
+        let conn = database::open_connection(&db_path).unwrap();
+        let mut s = app_with_due_item(conn, &RecordingOpener::default());
+        s.opener = Box::new(CommandOpener {
+            command: "spaced-programming-no-such-opener".into(),
+        });
+
+        // a missing browser is reported, the review still starts
+        assert!(start_review(&mut s).is_ok());
+        assert!(s.due_item.is_some());
+        let message = s.message.unwrap();
+        assert!(message.starts_with("could not open https://open.kattis.com/problems/hello"));
+        assert!(cleanup().is_ok());
+    }
+
+    /// util function that feeds key presses to the inbox screen, applying the changes they ask for
+    fn press_inbox_keys(s: &mut AppState, codes: &[event::KeyCode]) -> bool {
+        let mut closed = false;
+        for code in codes {
+            match inbox_key(&mut s.inbox, *code) {
+                ListKey::Edit => edit_inbox(s).unwrap(),
+                ListKey::Close => closed = true,
+                ListKey::Ignored => {}
+            }
+        }
+        closed
+    }
+
+    fn typed(text: &str) -> Vec<event::KeyCode> {
+        text.chars().map(event::KeyCode::Char).collect()
+    }
+
+    #[test]
+    #[serial]
+    fn inbox_screen_edits_inbox() {
+        use event::KeyCode::*;
+        let (db_path, cleanup) = create_temp_dir("inbox_screen_edits_inbox");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = AppState::new(conn, Box::new(opener), AppConfig::default());
+        load_inbox(&mut s).unwrap();
+
+        let mut keys = vec![Char('a')];
+        keys.extend(typed(
+            "http://kattis.com/problems/hello https://open.kattis.com/problems/faktor",
+        ));
+        keys.extend([Enter, Esc]);
+        assert!(!press_inbox_keys(&mut s, &keys));
+        assert!(s.inbox.input.is_none());
+        assert!(s.inbox.entries.len() == 2);
+        assert!(s.inbox.entries[0].url == "https://open.kattis.com/problems/hello");
+        assert!(s.message.as_deref() == Some("added 2, 0 already in the inbox, 0 already items"));
+
+        // move faktor to the top and tag it
+        press_inbox_keys(&mut s, &[Down, Char('K')]);
+        assert!(s.inbox.selected == 0);
+        assert!(s.inbox.entries[0].url == "https://open.kattis.com/problems/faktor");
+        let mut keys = vec![Char('t')];
+        keys.extend(typed("math, dp"));
+        keys.push(Enter);
+        press_inbox_keys(&mut s, &keys);
+        assert!(s.inbox.entries[0].tags == vec!["dp", "math"]);
+
+        // to the bottom and back to the top
+        press_inbox_keys(&mut s, &[Char('G')]);
+        assert!(s.inbox.selected == 1);
+        assert!(s.inbox.entries[1].url == "https://open.kattis.com/problems/faktor");
+        press_inbox_keys(&mut s, &[Char('g')]);
+        assert!(s.inbox.selected == 0);
+        assert!(s.inbox.entries[0].url == "https://open.kattis.com/problems/faktor");
+
+        // promote faktor and delete hello
+        press_inbox_keys(&mut s, &[Char('p')]);
+        assert!(database::get_item(&s.conn, 1).unwrap().tags == vec!["dp", "math"]);
+        assert!(press_inbox_keys(&mut s, &[Char('d'), Esc]));
+        assert!(s.inbox.entries.is_empty());
+        assert!(database::count_inbox_items(&s.conn).unwrap() == 0);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn inbox_drips_on_first_start_of_the_day() {
+        let (db_path, cleanup) = create_temp_dir("inbox_drips_on_first_start_of_the_day");
+        let conn = database::open_connection(&db_path).unwrap();
+        let urls = ["hello", "faktor", "autori"]
+            .iter()
+            .map(|problem| format!("https://open.kattis.com/problems/{problem}"))
+            .collect();
+        database::add_urls_to_inbox(&conn, urls).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = AppState::new(conn, Box::new(opener), AppConfig::default());
+        // off by default
+        assert!(drip_inbox(&mut s).unwrap() == 0);
+
+        s.config.drip.items_per_day = 2;
+        assert!(drip_inbox(&mut s).unwrap() == 2);
+        // restarting the app the same day does not promote more
+        assert!(drip_inbox(&mut s).unwrap() == 0);
+        refresh_main_screen(&mut s).unwrap();
+        assert!(s.n_new == Some(1));
+        assert!(s.n_due == Some(2));
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn tag_filter_restricts_the_session() {
+        let (db_path, cleanup) = create_temp_dir("tag_filter_restricts_the_session");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        for (url, tags) in [
+            ("https://open.kattis.com/problems/faktor", ["graphs", "dp"]),
+            (
+                "https://open.kattis.com/problems/autori",
+                ["graphs", "strings"],
+            ),
+        ] {
+            let id = database::insert_item(&s.conn, new_item(url, 20)).unwrap();
+            database::add_item_tags(&s.conn, id, &tags).unwrap();
+        }
+        let queued = |s: &AppState| -> Vec<String> {
+            let mut urls: Vec<String> = s
+                .due_items
+                .iter()
+                .flatten()
+                .map(|item| item.url.clone())
+                .collect();
+            urls.sort();
+            urls
+        };
+
+        s.tag_filter.tags = vec!["Graphs".into()];
+        refresh_main_screen(&mut s).unwrap();
+        assert!(s.n_due == Some(2));
+        assert!(
+            queued(&s)
+                == vec![
+                    "https://open.kattis.com/problems/autori",
+                    "https://open.kattis.com/problems/faktor"
+                ]
+        );
+        s.tag_filter = TagFilter {
+            tags: vec!["graphs".into(), "dp".into()],
+            all: true,
+        };
+        refresh_main_screen(&mut s).unwrap();
+        assert!(queued(&s) == vec!["https://open.kattis.com/problems/faktor"]);
+        // the session only starts items that passed the filter
+        start_review(&mut s).unwrap();
+        assert!(*opener.opened.borrow() == vec!["https://open.kattis.com/problems/faktor"]);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn backlog_is_spread_once_a_day() {
+        let (db_path, cleanup) = create_temp_dir("backlog_is_spread_once_a_day");
+        let conn = database::open_connection(&db_path).unwrap();
+        let mut s = app_with_due_item(conn, &RecordingOpener::default());
+        s.config.reviews_per_day = 1;
+        s.config.backlog.spread_days = 2;
+        for (url, tag) in [
+            ("https://open.kattis.com/problems/faktor", "graphs"),
+            ("https://open.kattis.com/problems/autori", "graphs"),
+            ("https://open.kattis.com/problems/sequences", "dp"),
+        ] {
+            let item = Item {
+                scheduling_data: SchedulingData {
+                    times_reviewed: 1,
+                    ..SchedulingData::default()
+                },
+                last_review: Some(10),
+                ..new_item(url, 20)
+            };
+            let id = database::insert_item(&s.conn, item).unwrap();
+            database::add_item_tags(&s.conn, id, &[tag]).unwrap();
+        }
+
+        // only the reviews of the session's tags are spread
+        s.tag_filter.tags = vec!["graphs".into()];
+        assert!(spread_backlog(&mut s).unwrap() == 1);
+        let now = database::current_timestamp();
+        let due = database::get_due_items(&s.conn, now).unwrap();
+        assert!(due.len() == 3);
+        // and only once a day, even though the other tags have a backlog too
+        s.tag_filter = TagFilter::default();
+        assert!(spread_backlog(&mut s).unwrap() == 0);
+        assert!(database::get_due_items(&s.conn, now).unwrap().len() == 3);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn queue_follows_the_order() {
+        let (db_path, cleanup) = create_temp_dir("queue_follows_the_order");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        let item = Item {
+            scheduling_data: SchedulingData {
+                times_reviewed: 1,
+                difficulty: 30.0,
+                ..SchedulingData::default()
+            },
+            ..new_item("https://open.kattis.com/problems/faktor", 20)
+        };
+        database::insert_item(&s.conn, item).unwrap();
+        let next_up = |s: &AppState| s.due_items.as_ref().unwrap().last().unwrap().id;
+
+        s.config.new_items_per_day = 0;
+        s.config.queue.order = planner::QueueOrder::WeakestMemory;
+        refresh_main_screen(&mut s).unwrap();
+        assert!(next_up(&s) == 2);
+        // hello is new, with a second review the order decides between the two
+        s.config.new_items_per_day = 5;
+        let mut hello = database::get_item(&s.conn, 1).unwrap();
+        hello.scheduling_data.times_reviewed = 1;
+        database::update_items(&mut s.conn, vec![hello]).unwrap();
+        refresh_main_screen(&mut s).unwrap();
+        assert!(next_up(&s) == 1);
+        s.config.queue.order = planner::QueueOrder::HardestFirst;
+        refresh_main_screen(&mut s).unwrap();
+        assert!(next_up(&s) == 2);
+        assert!(s.plan.as_ref().unwrap().order == planner::QueueOrder::HardestFirst);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn grading_reschedules_item() {
+        let (db_path, cleanup) = create_temp_dir("grading_reschedules_item");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        start_review(&mut s).unwrap();
+        let id = s.due_item.as_ref().unwrap().id;
+
+        let before = database::current_timestamp();
+        s.duration = Some(Duration::from_secs(300));
+        assert!(grade_review(&mut s, UserReview::JustEnough).is_ok());
+        let item = database::get_item(&s.conn, id).unwrap();
+        assert!(item.scheduling_data.times_reviewed == 1);
+        // the review ended up in the log
+        let reviews = database::get_item_reviews(&s.conn, id).unwrap();
+        assert!(reviews.len() == 1);
+        assert!(reviews[0].duration == Some(Duration::from_secs(300)));
+        assert!(reviews[0].interval_after == item.scheduling_data.interval);
+        assert!(item.due >= before + scheduling::SECONDS_PER_DAY);
+        // the item is no longer due and the main screen knows it
+        assert!(s.due_item.is_none());
+        assert!(s.n_due == Some(0));
+        assert!(s.due_items.as_ref().unwrap().is_empty());
+        assert!(matches!(s.review_result, Some(UserReview::JustEnough)));
+        // and counts it as done for today
+        assert!(s.plan.as_ref().unwrap().done.new == 1);
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn undo_reviews_in_reverse_order() {
+        let (db_path, cleanup) = create_temp_dir("undo_reviews_in_reverse_order");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        let item = new_item("https://open.kattis.com/problems/faktor", 20);
+        database::insert_item(&s.conn, item).unwrap();
+        refresh_main_screen(&mut s).unwrap();
+
+        undo_review(&mut s).unwrap();
+        assert!(s.message.as_deref() == Some("no review to undo"));
+
+        let mut graded = vec![];
+        for review in [UserReview::TooHard, UserReview::TooEasy] {
+            start_review(&mut s).unwrap();
+            graded.push(s.due_item.as_ref().unwrap().id);
+            grade_review(&mut s, review).unwrap();
+        }
+        assert!(s.n_due == Some(0));
+
+        // the last review is taken back first and its item is the next one we review
+        for (n_due, id) in [(1, graded[1]), (2, graded[0])] {
+            undo_review(&mut s).unwrap();
+            assert!(s.n_due == Some(n_due));
+            assert!(s.due_items.as_ref().unwrap().last().unwrap().id == id);
+            let item = database::get_item(&s.conn, id).unwrap();
+            assert!(item.scheduling_data.times_reviewed == 0);
+            assert!(database::get_item_reviews(&s.conn, id).unwrap().is_empty());
+        }
+        assert!(s.undo.is_empty());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn suspended_items_leave_the_queue() {
+        let (db_path, cleanup) = create_temp_dir("suspended_items_leave_the_queue");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        let keys = s.config.key_bindings.clone();
+        assert!(status_key(&keys, keys.edit_notes).is_none());
+
+        // bury the item, it is back tomorrow
+        start_review(&mut s).unwrap();
+        s.new_status = status_key(&keys, keys.bury);
+        set_item_status(&mut s).unwrap();
+        assert!(s.n_due == Some(0));
+        assert!(s.due_item.is_none());
+        let tomorrow = (database::day_of(database::current_timestamp()) + 1) * SECONDS_PER_DAY;
+        let buried = database::get_suspended_items(&s.conn, tomorrow - 1).unwrap();
+        assert!(buried[0].status == ItemStatus::Buried(tomorrow));
+        assert!(database::count_due_items(&s.conn, tomorrow).unwrap() == 1);
+
+        // suspend it from the suspended items screen, then bring it back
+        load_suspended(&mut s).unwrap();
+        assert!(s.suspended.items.len() == 1);
+        for (code, status) in [
+            (event::KeyCode::Char(keys.suspend), ItemStatus::Suspended),
+            (event::KeyCode::Char(keys.retire), ItemStatus::Retired),
+        ] {
+            assert!(matches!(
+                suspended_key(&mut s.suspended, &keys, code),
+                ListKey::Edit
+            ));
+            edit_suspended(&mut s).unwrap();
+            assert!(s.suspended.items[0].status == status);
+        }
+        // a binding on a selection key still does what it is configured for
+        let rebound = config::KeyBindings {
+            unsuspend: 'j',
+            ..keys.clone()
+        };
+        suspended_key(&mut s.suspended, &rebound, event::KeyCode::Char('j'));
+        assert!(s.suspended.action.map(|(_, status)| status) == Some(ItemStatus::Active));
+        edit_suspended(&mut s).unwrap();
+        assert!(s.suspended.items.is_empty());
+        refresh_main_screen(&mut s).unwrap();
+        assert!(s.n_due == Some(1));
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn judge_verdict_is_recorded_with_review() {
+        let (db_path, cleanup) = create_temp_dir("judge_verdict_is_recorded_with_review");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        start_review(&mut s).unwrap();
+        let id = s.due_item.as_ref().unwrap().id;
+
+        // without samples there is nothing to judge
+        s.config.judge.solution_dir = db_path.parent().unwrap().to_path_buf();
+        run_judge(&mut s).unwrap();
+        assert!(s.judge_report.is_none());
+        assert!(s.message.as_deref() == Some("no sample tests for this problem"));
+
+        database::add_sample_test(&s.conn, id, "", "Hello World!\n").unwrap();
+        let solution = db_path.with_file_name("hello.sh");
+        std::fs::write(&solution, "echo Hello World!\n").unwrap();
+        s.config.judge.languages.insert(
+            "sh".into(),
+            config::LanguageConfig {
+                compile: None,
+                run: "sh {src}".into(),
+            },
+        );
+        run_judge(&mut s).unwrap();
+        let report = s.judge_report.as_ref().unwrap();
+        assert!(report.solution == solution);
+        assert!(report.verdict == types::Verdict::Accepted);
+
+        assert!(grade_review(&mut s, UserReview::JustEnough).is_ok());
+        let reviews = database::get_item_reviews(&s.conn, id).unwrap();
+        assert!(reviews[0].verdict == Some(types::Verdict::Accepted));
+        // the next review starts without a verdict
+        assert!(s.judge_report.is_none());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    fn main_keys_map_to_actions() {
+        use event::KeyCode::{Char, Enter, Esc};
+        let keys = config::KeyBindings::default();
+        let action = |code, has_due| main_key(&keys, code, has_due);
+        assert!(action(Char(keys.review), true) == Some(MainAction::StartReview));
+        assert!(action(Char(' '), true) == Some(MainAction::StartReview));
+        // nothing to review
+        assert!(action(Char(' '), false).is_none());
+        assert!(action(Char(keys.undo_review), false) == Some(MainAction::UndoReview));
+        assert!(action(Char(keys.queue_order), false) == Some(MainAction::CycleOrder));
+        assert!(action(Esc, false) == Some(MainAction::Quit));
+        assert!(action(Enter, true).is_none());
+        // every main screen leaves through the same transitions
+        let main = Machine::new(WelcomeScreen).transition(StartApp);
+        let state = main_transition(main.clone(), MainAction::StartReview);
+        assert!(matches!(state, ReviewInfoScreenByStartReview(_)));
+        let main = main.transition(CycleOrder);
+        assert!(matches!(
+            main_transition(main, MainAction::ShowInbox),
+            InboxScreenByShowInbox(_)
+        ));
+    }
+
+    #[test]
+    fn eval_screen_selects_suggested_grade() {
+        let mut selected = Some(grading::suggest_grade(
+            Some(Duration::from_secs(10 * 60)),
+            Duration::from_secs(30 * 60),
+            Some(types::Verdict::Accepted),
+        ));
+        assert!(matches!(selected, Some(UserReview::TooEasy)));
+        // we can still pick another grade
+        select_grade(&mut selected, true);
+        assert!(matches!(selected, Some(UserReview::JustEnough)));
+        let mut selected = None;
+        select_grade(&mut selected, false);
+        assert!(matches!(selected, Some(UserReview::TooEasy)));
+    }
+
+    #[test]
+    #[serial]
+    fn attached_solution_is_archived_with_review() {
+        let (db_path, cleanup) = create_temp_dir("attached_solution_is_archived_with_review");
+        let conn = database::open_connection(&db_path).unwrap();
+        let opener = RecordingOpener::default();
+        let mut s = app_with_due_item(conn, &opener);
+        s.config.judge.solution_dir = db_path.parent().unwrap().to_path_buf();
+        start_review(&mut s).unwrap();
+        let id = s.due_item.as_ref().unwrap().id;
+        assert!(s.solutions.is_empty());
+
+        attach_solution(&mut s);
+        assert!(s.attached_solution.is_none());
+        std::fs::write(db_path.with_file_name("hello.cpp"), "int main() {}\n").unwrap();
+        attach_solution(&mut s);
+        assert!(s.message.as_deref() == Some("hello.cpp is saved with the review"));
+        assert!(grade_review(&mut s, UserReview::JustEnough).is_ok());
+
+        let solutions = database::get_item_solutions(&s.conn, id).unwrap();
+        let reviews = database::get_item_reviews(&s.conn, id).unwrap();
+        assert!(solutions.len() == 1);
+        assert!(solutions[0].language == "cpp");
+        assert!(solutions[0].source == "int main() {}\n");
+        assert!(solutions[0].review_id == Some(reviews[0].id));
+        assert!(s.attached_solution.is_none());
+        assert!(cleanup().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn unreadable_solutions_keep_the_review() {
+        let (db_path, cleanup) = create_temp_dir("unreadable_solutions_keep_the_review");
+        let conn = database::open_connection(&db_path).unwrap();
+        let mut s = app_with_due_item(conn, &RecordingOpener::default());
+        start_review(&mut s).unwrap();
+
+        s.config.judge.solution_dir = db_path.with_file_name("no_such_dir");
+        attach_solution(&mut s);
+        assert!(s.message.as_deref().unwrap().starts_with("JudgeError"));
+        s.config.judge.solution_dir = db_path.parent().unwrap().to_path_buf();
+        std::fs::write(db_path.with_file_name("hello.py"), [0xff, 0xfe]).unwrap();
+        attach_solution(&mut s);
+        assert!(s.message.as_deref().unwrap().starts_with("could not read"));
+        assert!(s.due_item.is_some() && s.attached_solution.is_none());
+        assert!(cleanup().is_ok());
+    }
 }
@@ -0,0 +1,144 @@
+//! This module turns the markdown of our item notes into styled tui text. Only the parts of
+//! markdown that make sense in a terminal are supported: headings, lists, code fences and inline
+//! code. Everything else is shown as written.
+
+// external imports
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans, Text};
+
+fn heading_style(level: usize) -> Style {
+    let style = Style::default().add_modifier(Modifier::BOLD);
+    match level {
+        1 => style.fg(Color::Yellow).add_modifier(Modifier::UNDERLINED),
+        2 => style.fg(Color::Yellow),
+        _ => style,
+    }
+}
+
+fn code_style() -> Style {
+    Style::default().fg(Color::Cyan)
+}
+
+/// renders {markdown} line by line
+pub fn render(markdown: &str) -> Text<'static> {
+    let mut lines = vec![];
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            // the fence itself is not shown, only the code between the fences
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(Spans::from(Span::styled(format!("  {line}"), code_style())));
+        } else {
+            lines.push(render_line(line));
+        }
+    }
+    Text::from(lines)
+}
+
+fn render_line(line: &str) -> Spans<'static> {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+
+    // headings: '#' up to '######' followed by a space
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+        return Spans::from(Span::styled(
+            trimmed[level..].trim().to_string(),
+            heading_style(level),
+        ));
+    }
+
+    // bullet lists ('-', '*' or '+') get a proper bullet, numbered lists keep their number
+    let (marker, rest) = if let Some(rest) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| trimmed.strip_prefix(bullet))
+    {
+        (format!("{indent}• "), rest)
+    } else if let Some((number, rest)) = numbered_item(trimmed) {
+        (format!("{indent}{number}. "), rest)
+    } else {
+        (indent.to_string(), trimmed)
+    };
+
+    let mut spans = vec![];
+    if !marker.is_empty() {
+        spans.push(Span::styled(marker, Style::default().fg(Color::Yellow)));
+    }
+    spans.extend(render_inline(rest));
+    Spans::from(spans)
+}
+
+/// splits "12. text" into ("12", "text")
+fn numbered_item(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .map(|rest| (&line[..digits], rest))
+}
+
+/// styles `inline code`, an unmatched backtick is shown as is
+fn render_inline(text: &str) -> Vec<Span<'static>> {
+    let mut spans = vec![];
+    let mut parts = text.split('`').peekable();
+    let mut is_code = false;
+    while let Some(part) = parts.next() {
+        // the last part of an odd split has no closing backtick
+        let unclosed = is_code && parts.peek().is_none();
+        if unclosed {
+            spans.push(Span::raw(format!("`{part}")));
+        } else if is_code {
+            spans.push(Span::styled(part.to_string(), code_style()));
+        } else if !part.is_empty() {
+            spans.push(Span::raw(part.to_string()));
+        }
+        is_code = !is_code;
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// util function to get the text of each rendered line
+    fn contents(text: &Text) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|spans| spans.0.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn headings_lose_their_hashes() {
+        let text = render("# Faktor\n## idea\n#not a heading");
+        assert!(contents(&text) == vec!["Faktor", "idea", "#not a heading"]);
+        assert!(text.lines[0].0[0].style == heading_style(1));
+        assert!(text.lines[1].0[0].style == heading_style(2));
+    }
+
+    #[test]
+    fn lists_get_markers() {
+        let text = render("- first\n  * nested\n3. third");
+        assert!(contents(&text) == vec!["• first", "  • nested", "3. third"]);
+    }
+
+    #[test]
+    fn code_fences_are_styled_verbatim() {
+        let text = render("before\n```rust\n# not a heading\n```\nafter");
+        assert!(contents(&text) == vec!["before", "  # not a heading", "after"]);
+        assert!(text.lines[1].0[0].style == code_style());
+    }
+
+    #[test]
+    fn inline_code() {
+        let text = render("use `i64` here, not `i32");
+        assert!(contents(&text) == vec!["use i64 here, not `i32"]);
+        assert!(text.lines[0].0[1].style == code_style());
+    }
+}
@@ -0,0 +1,46 @@
+//! This module launches problem urls in the browser. Reviews go through the UrlOpener trait so
+//! tests can check what would have been opened without starting a browser.
+
+// stdlib imports
+use std::process::{Command, Stdio};
+
+pub trait UrlOpener {
+    fn open(&self, url: &str) -> std::io::Result<()>;
+}
+
+/// opens urls with a command such as xdg-open (the desktop's preferred application for them).
+/// The command may contain arguments, the url is passed last.
+pub struct CommandOpener {
+    pub command: String,
+}
+
+impl UrlOpener for CommandOpener {
+    fn open(&self, url: &str) -> std::io::Result<()> {
+        let mut parts = self.command.split_whitespace();
+        let program = parts.next().unwrap_or("xdg-open");
+        // we don't wait for the browser, and it should not write all over our tui
+        Command::new(program)
+            .args(parts)
+            .arg(url)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        Ok(())
+    }
+}
+
+/// remembers every url it was asked to open
+#[cfg(test)]
+#[derive(Default, Clone)]
+pub struct RecordingOpener {
+    pub opened: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+}
+
+#[cfg(test)]
+impl UrlOpener for RecordingOpener {
+    fn open(&self, url: &str) -> std::io::Result<()> {
+        self.opened.borrow_mut().push(url.to_string());
+        Ok(())
+    }
+}
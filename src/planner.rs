@@ -0,0 +1,459 @@
+//! This module plans the reviews of a day: which of the due items we get to today and in what
+//! order, given the daily limits in the config and what we already did today.
+
+// stdlib imports
+use std::collections::HashMap;
+use std::time::Duration;
+// external imports
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::Deserialize;
+// internal imports
+use crate::{
+    config::{AppConfig, GradingConfig},
+    grading,
+    scheduling::{self, SECONDS_PER_DAY},
+    types::{DoneToday, Item, ItemId, SessionPlan},
+};
+
+/// The order we review the due items in
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueOrder {
+    OldestDue,
+    // lowest memory strength first
+    WeakestMemory,
+    // highest difficulty first
+    HardestFirst,
+    // shuffled with the seed in the config, the same seed and day give the same queue
+    Random,
+    // items take turns by their first tag, each tag in oldest due order
+    TagRoundRobin,
+}
+
+impl QueueOrder {
+    const ALL: [QueueOrder; 5] = [
+        QueueOrder::OldestDue,
+        QueueOrder::WeakestMemory,
+        QueueOrder::HardestFirst,
+        QueueOrder::Random,
+        QueueOrder::TagRoundRobin,
+    ];
+
+    /// the order after this one, switching on the main screen cycles through them
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|order| *order == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueueOrder::OldestDue => "oldest due",
+            QueueOrder::WeakestMemory => "weakest memory",
+            QueueOrder::HardestFirst => "hardest first",
+            QueueOrder::Random => "random",
+            QueueOrder::TagRoundRobin => "round robin by tag",
+        }
+    }
+}
+
+/// sorts {items} by {order}, oldest due first where the order does not decide. {seed} drives the
+/// random order.
+pub fn order_items(items: Vec<Item>, order: QueueOrder, seed: u64) -> Vec<Item> {
+    let mut items = items;
+    items.sort_by_key(|item| (item.due, item.id));
+    match order {
+        QueueOrder::OldestDue => {}
+        QueueOrder::WeakestMemory => items.sort_by(|a, b| {
+            let strength = |item: &Item| item.scheduling_data.memory_strength;
+            strength(a).total_cmp(&strength(b))
+        }),
+        QueueOrder::HardestFirst => items.sort_by(|a, b| {
+            let difficulty = |item: &Item| item.scheduling_data.difficulty;
+            difficulty(b).total_cmp(&difficulty(a))
+        }),
+        QueueOrder::Random => items.shuffle(&mut StdRng::seed_from_u64(seed)),
+        // tags are sorted, so an item counts towards its first tag in alphabetical order
+        QueueOrder::TagRoundRobin => items = round_robin(items, |item| item.tags.first().cloned()),
+    }
+    items
+}
+
+/// {items} grouped by {key}, the groups taking turns in the order their first item comes in. Each
+/// group keeps the order of its items.
+pub fn round_robin<T, K: PartialEq>(items: Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut groups: Vec<(K, Vec<T>)> = vec![];
+    for item in items {
+        let item_key = key(&item);
+        match groups.iter_mut().find(|(other, _)| *other == item_key) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((item_key, vec![item])),
+        }
+    }
+    let mut groups: Vec<_> = groups
+        .into_iter()
+        .map(|(_, group)| group.into_iter())
+        .collect();
+    let mut ordered = vec![];
+    loop {
+        let before = ordered.len();
+        ordered.extend(groups.iter_mut().filter_map(|group| group.next()));
+        if ordered.len() == before {
+            return ordered;
+        }
+    }
+}
+
+/// whether reviewing {item} would be the first time we solve it
+pub fn is_new(item: &Item) -> bool {
+    item.scheduling_data.times_reviewed == 0
+}
+
+/// how long reviewing {item} will probably take: its own average solve time, else the average over
+/// all items, else its target time
+pub fn estimate(
+    item: &Item,
+    averages: &HashMap<ItemId, Duration>,
+    overall: Option<Duration>,
+    config: &GradingConfig,
+) -> Duration {
+    averages
+        .get(&item.id)
+        .copied()
+        .or(overall)
+        .unwrap_or_else(|| grading::target_time(item, config))
+}
+
+/// sorts {items} by how likely we are to have forgotten them at {now}, most forgotten first
+pub fn most_forgotten_first(items: &mut [Item], now: u64) {
+    let recall = |item: &Item| {
+        let elapsed_days = scheduling::days_since_review(item, now);
+        scheduling::recall_probability(&item.scheduling_data, elapsed_days)
+    };
+    items.sort_by(|a, b| recall(a).total_cmp(&recall(b)));
+}
+
+/// the {due} items we review at {now}, first to last, and what they add up to. Both reviews and
+/// new items are put in the configured order and capped at what {done} left of their daily limits,
+/// new items are spread evenly between the reviews, and the queue ends before the first item that
+/// would take us past the daily minutes. With a backlog of reviews the recovery mode keeps the
+/// most forgotten ones instead.
+pub fn plan_session(
+    due: Vec<Item>,
+    done: &DoneToday,
+    config: &AppConfig,
+    now: u64,
+    estimate: impl Fn(&Item) -> Duration,
+) -> (Vec<Item>, SessionPlan) {
+    let n_due = due.len();
+    let (new, reviews): (Vec<Item>, Vec<Item>) = due.into_iter().partition(is_new);
+    // a new random order every day, but the same one for every plan of the day
+    let seed = config.queue.seed.wrapping_add(now / SECONDS_PER_DAY);
+    let new = order_items(new, config.queue.order, seed);
+    let mut reviews = order_items(reviews, config.queue.order, seed);
+    let reviews_left = config.reviews_per_day.saturating_sub(done.reviews) as usize;
+    if config.backlog.recovery && reviews.len() > reviews_left {
+        most_forgotten_first(&mut reviews, now);
+    }
+    let new_left = config.new_items_per_day.saturating_sub(done.new) as usize;
+    let time_left = config
+        .minutes_per_day
+        .map(|minutes| Duration::from_secs(minutes as u64 * 60).saturating_sub(done.time));
+
+    let mut queue = interleave(
+        reviews.into_iter().take(reviews_left).collect(),
+        new.into_iter().take(new_left).collect(),
+    );
+    let mut estimated = Duration::ZERO;
+    let mut n_fitting = 0;
+    for item in &queue {
+        let time = estimate(item);
+        if time_left.is_some_and(|left| estimated + time > left) {
+            break;
+        }
+        estimated += time;
+        n_fitting += 1;
+    }
+    queue.truncate(n_fitting);
+
+    let n_new = queue.iter().filter(|item| is_new(item)).count();
+    let plan = SessionPlan {
+        n_reviews: queue.len() - n_new,
+        n_new,
+        estimated,
+        held_back: n_due - queue.len(),
+        done: *done,
+        order: config.queue.order,
+    };
+    (queue, plan)
+}
+
+/// the reviews among {due} that do not fit into what {done} left of today's limit, with new due
+/// dates: the most forgotten ones stay due today, the next reviews_per_day move to the start of
+/// tomorrow, the ones after them to the day after, and so on up to backlog.spread_days days ahead
+/// where the rest piles up. Nothing moves if spreading is turned off.
+pub fn spread_backlog(due: Vec<Item>, done: &DoneToday, config: &AppConfig, now: u64) -> Vec<Item> {
+    let max_days = config.backlog.spread_days as usize;
+    let mut reviews: Vec<Item> = due.into_iter().filter(|item| !is_new(item)).collect();
+    let reviews_left = config.reviews_per_day.saturating_sub(done.reviews) as usize;
+    if max_days == 0 || reviews.len() <= reviews_left {
+        return vec![];
+    }
+    most_forgotten_first(&mut reviews, now);
+    let today = now / SECONDS_PER_DAY;
+    let per_day = config.reviews_per_day.max(1) as usize;
+    reviews
+        .into_iter()
+        .skip(reviews_left)
+        .enumerate()
+        .map(|(i, item)| {
+            let days_ahead = (1 + i / per_day).min(max_days) as u64;
+            Item {
+                due: (today + days_ahead) * SECONDS_PER_DAY,
+                ..item
+            }
+        })
+        .collect()
+}
+
+/// merges {new} into {reviews} so the new ones are evenly spread, keeping the order of both
+fn interleave<T>(reviews: Vec<T>, new: Vec<T>) -> Vec<T> {
+    let (n_new, total) = (new.len(), reviews.len() + new.len());
+    let (mut reviews, mut new) = (reviews.into_iter(), new.into_iter());
+    let mut queue = Vec::with_capacity(total);
+    let mut taken_new = 0;
+    for i in 0..total {
+        // the k-th new item goes to the middle of the k-th of n_new equal parts of the queue
+        let item = if (2 * taken_new + 1) * total <= (2 * i + 1) * n_new {
+            taken_new += 1;
+            new.next().or_else(|| reviews.next())
+        } else {
+            reviews.next().or_else(|| new.next())
+        };
+        queue.extend(item);
+    }
+    queue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::new_item;
+    use crate::types::SchedulingData;
+
+    /// an item reviewed {times_reviewed} times that became due at {due}, a day after its last
+    /// review
+    fn item_due(id: ItemId, times_reviewed: i32, due: u64) -> Item {
+        Item {
+            due,
+            last_review: (times_reviewed > 0).then(|| due.saturating_sub(SECONDS_PER_DAY)),
+            ..item(id, times_reviewed)
+        }
+    }
+
+    fn item(id: ItemId, times_reviewed: i32) -> Item {
+        Item {
+            id,
+            scheduling_data: SchedulingData {
+                times_reviewed,
+                ..SchedulingData::default()
+            },
+            ..new_item(&format!("https://open.kattis.com/problems/{id}"), 0)
+        }
+    }
+
+    fn ids(queue: &[Item]) -> Vec<ItemId> {
+        queue.iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn new_items_are_spread_out() {
+        assert!(interleave(vec![1, 2, 3, 4], vec![10, 20]) == vec![1, 10, 2, 3, 20, 4]);
+        assert!(interleave(vec![1, 2], vec![10]) == vec![1, 10, 2]);
+        assert!(interleave(vec![], vec![10, 20]) == vec![10, 20]);
+        assert!(interleave(vec![1, 2], vec![]) == vec![1, 2]);
+        // more new items than reviews
+        assert!(interleave(vec![1], vec![10, 20, 30]) == vec![1, 10, 20, 30]);
+    }
+
+    #[test]
+    fn limits_cap_the_queue() {
+        let config = AppConfig {
+            reviews_per_day: 3,
+            new_items_per_day: 2,
+            ..AppConfig::default()
+        };
+        let due = || {
+            (1..=5)
+                .map(|id| item(id, 1))
+                .chain((6..=9).map(|id| item(id, 0)))
+        };
+        let minute = |_: &Item| Duration::from_secs(60);
+
+        let done = DoneToday::default();
+        let (queue, plan) = plan_session(due().collect(), &done, &config, 0, minute);
+        assert!(ids(&queue) == vec![1, 6, 2, 3, 7]);
+        assert!(plan.n_reviews == 3 && plan.n_new == 2);
+        assert!(plan.estimated == Duration::from_secs(5 * 60));
+        assert!(plan.held_back == 4);
+
+        // what we did earlier today counts towards the limits
+        let done = DoneToday {
+            reviews: 2,
+            new: 2,
+            time: Duration::ZERO,
+        };
+        let (queue, plan) = plan_session(due().collect(), &done, &config, 0, minute);
+        assert!(ids(&queue) == vec![1]);
+        assert!(plan.n_new == 0 && plan.held_back == 8);
+    }
+
+    #[test]
+    fn minutes_cap_the_queue() {
+        let config = AppConfig {
+            minutes_per_day: Some(60),
+            ..AppConfig::default()
+        };
+        let due: Vec<Item> = (1..=4).map(|id| item(id, 1)).collect();
+        let done = DoneToday {
+            reviews: 1,
+            new: 0,
+            time: Duration::from_secs(25 * 60),
+        };
+        // 35 minutes are left, the second item does not fit and neither does anything after it
+        let estimate = |item: &Item| Duration::from_secs([0, 20, 30, 5, 5][item.id as usize] * 60);
+        let (queue, plan) = plan_session(due, &done, &config, 0, estimate);
+        assert!(ids(&queue) == vec![1]);
+        assert!(plan.estimated == Duration::from_secs(20 * 60));
+        assert!(plan.held_back == 3);
+    }
+
+    #[test]
+    fn backlog_recovery_keeps_the_most_forgotten() {
+        let config = AppConfig {
+            reviews_per_day: 2,
+            ..AppConfig::default()
+        };
+        let now = 19000 * SECONDS_PER_DAY;
+        // overdue by 1, 6, 3 and 0 days
+        let due: Vec<Item> = [1, 6, 3, 0]
+            .iter()
+            .enumerate()
+            .map(|(i, days)| item_due(i as ItemId + 1, 1, now - days * SECONDS_PER_DAY))
+            .collect();
+        let minute = |_: &Item| Duration::from_secs(60);
+        let done = DoneToday::default();
+        let (queue, _) = plan_session(due, &done, &config, now, minute);
+        assert!(ids(&queue) == vec![2, 3]);
+
+        // without a backlog the configured order applies, oldest due first by default
+        let due: Vec<Item> = (1..=2).map(|id| item_due(id, 1, now - id)).collect();
+        let (queue, _) = plan_session(due, &done, &config, now, minute);
+        assert!(ids(&queue) == vec![2, 1]);
+    }
+
+    #[test]
+    fn backlog_spreads_over_the_coming_days() {
+        let mut config = AppConfig {
+            reviews_per_day: 2,
+            ..AppConfig::default()
+        };
+        let now = 19000 * SECONDS_PER_DAY + 100;
+        // item 1 is the most overdue, item 7 the least, item 8 is new and stays
+        let due = || {
+            (1..=7)
+                .map(|id| item_due(id, 1, now - (10 - id) * SECONDS_PER_DAY))
+                .chain([item_due(8, 0, now)])
+                .collect::<Vec<Item>>()
+        };
+        let done = DoneToday {
+            reviews: 1,
+            ..DoneToday::default()
+        };
+        assert!(spread_backlog(due(), &done, &config, now).is_empty());
+
+        config.backlog.spread_days = 2;
+        let moved = spread_backlog(due(), &done, &config, now);
+        // one review is left today, the next two go to tomorrow and the rest piles up on day two
+        let days: Vec<(ItemId, u64)> = moved
+            .iter()
+            .map(|item| (item.id, item.due / SECONDS_PER_DAY - 19000))
+            .collect();
+        assert!(days == vec![(2, 1), (3, 1), (4, 2), (5, 2), (6, 2), (7, 2)]);
+    }
+
+    #[test]
+    fn moved_items_keep_their_last_review() {
+        let now = 19000 * SECONDS_PER_DAY;
+        // the backlog moved item 1 to today although we last saw it a month ago
+        let mut moved = item_due(1, 1, now);
+        moved.last_review = Some(now - 30 * SECONDS_PER_DAY);
+        let mut items = vec![item_due(2, 1, now - 5 * SECONDS_PER_DAY), moved];
+        most_forgotten_first(&mut items, now);
+        assert!(ids(&items) == vec![1, 2]);
+    }
+
+    #[test]
+    fn queue_orders() {
+        let items = || {
+            let mut items = vec![];
+            for (id, due, strength, difficulty, tag) in [
+                (1, 30, 100.0, 5.0, "dp"),
+                (2, 10, 300.0, 20.0, "dp"),
+                (3, 20, 200.0, 10.0, "graphs"),
+                (4, 40, 50.0, 15.0, "dp"),
+                (5, 50, 150.0, 1.0, ""),
+            ] {
+                let mut item = item_due(id, 1, due);
+                item.scheduling_data.memory_strength = strength;
+                item.scheduling_data.difficulty = difficulty;
+                item.tags = vec![tag.to_string()]
+                    .into_iter()
+                    .filter(|t| !t.is_empty())
+                    .collect();
+                items.push(item);
+            }
+            items
+        };
+        let order = |order| ids(&order_items(items(), order, 7));
+        assert!(order(QueueOrder::OldestDue) == vec![2, 3, 1, 4, 5]);
+        assert!(order(QueueOrder::WeakestMemory) == vec![4, 1, 5, 3, 2]);
+        assert!(order(QueueOrder::HardestFirst) == vec![2, 4, 3, 1, 5]);
+        // dp, graphs and untagged take turns, in the order of their oldest item
+        assert!(order(QueueOrder::TagRoundRobin) == vec![2, 3, 5, 1, 4]);
+
+        // the same seed gives the same shuffle
+        let random = order(QueueOrder::Random);
+        assert!(random == order(QueueOrder::Random));
+        let mut sorted = random.clone();
+        sorted.sort();
+        assert!(sorted == vec![1, 2, 3, 4, 5]);
+        let shuffles: Vec<Vec<ItemId>> = (0..10)
+            .map(|seed| ids(&order_items(items(), QueueOrder::Random, seed)))
+            .collect();
+        assert!(shuffles.iter().any(|shuffle| *shuffle != shuffles[0]));
+    }
+
+    #[test]
+    fn orders_cycle() {
+        let mut order = QueueOrder::OldestDue;
+        for _ in 0..QueueOrder::ALL.len() {
+            order = order.next();
+            assert!(order.name() != "");
+        }
+        assert!(order == QueueOrder::OldestDue);
+    }
+
+    #[test]
+    fn estimates_fall_back_to_averages_and_targets() {
+        let config = GradingConfig::default();
+        let averages = HashMap::from([(1, Duration::from_secs(300))]);
+        let overall = Some(Duration::from_secs(600));
+        assert!(estimate(&item(1, 1), &averages, overall, &config) == Duration::from_secs(300));
+        assert!(estimate(&item(2, 0), &averages, overall, &config) == Duration::from_secs(600));
+        let target = Duration::from_secs(config.target_minutes as u64 * 60);
+        assert!(estimate(&item(2, 0), &averages, None, &config) == target);
+    }
+}
@@ -0,0 +1,641 @@
+//! This module turns a graded review into new scheduling data and a due date. Every algorithm
+//! implements [Scheduler]. The counters and the interval of an item are shared by all of them,
+//! whatever else an algorithm needs to remember is kept in the item's [SchedulerState].
+
+// external imports
+use serde::Deserialize;
+use spaced_rs::{compute_interval, schedule, SchedulingData, UpdateParameters, UserReview};
+// internal imports
+use crate::types::{Item, SchedulerState};
+
+/// the probability that we still remember an item when it becomes due
+pub const RECALL_PROBABILITY: f32 = 0.9;
+pub const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// A way of scheduling reviews
+pub trait Scheduler {
+    /// the state of an item that was never reviewed
+    fn initial_state(&self) -> SchedulerState;
+
+    /// the scheduling data and state of an item after a review graded {review}, {elapsed_days}
+    /// after the review before it. {state} may belong to another algorithm when the config
+    /// changed, in which case the item's interval is carried over.
+    fn next_state(
+        &self,
+        data: &SchedulingData,
+        state: &SchedulerState,
+        review: &UserReview,
+        elapsed_days: f32,
+    ) -> (SchedulingData, SchedulerState);
+
+    /// the timestamp an item reviewed at {now} becomes due again
+    fn due(&self, data: &SchedulingData, now: u64) -> u64 {
+        due_after(now, data.interval)
+    }
+}
+
+/// The algorithms to choose from in the config
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Algorithm {
+    SpacedRs,
+    Sm2,
+    Leitner,
+    Fsrs,
+}
+
+/// spaced-rs, what every item was scheduled with before there was a choice
+pub struct SpacedRsScheduler;
+/// SuperMemo 2, the algorithm anki grew out of
+pub struct Sm2Scheduler;
+/// cards move up a box when recalled and back to the first box when not
+pub struct LeitnerScheduler;
+/// the free spaced repetition scheduler (FSRS 4.5) with its default weights
+pub struct FsrsScheduler;
+
+pub fn scheduler(algorithm: Algorithm) -> &'static dyn Scheduler {
+    match algorithm {
+        Algorithm::SpacedRs => &SpacedRsScheduler,
+        Algorithm::Sm2 => &Sm2Scheduler,
+        Algorithm::Leitner => &LeitnerScheduler,
+        Algorithm::Fsrs => &FsrsScheduler,
+    }
+}
+
+/// spaced-rs takes its review by value and UserReview is neither Clone nor Copy
+pub fn copy_review(review: &UserReview) -> UserReview {
+    match review {
+        UserReview::TooHard => UserReview::TooHard,
+        UserReview::JustEnough => UserReview::JustEnough,
+        UserReview::TooEasy => UserReview::TooEasy,
+    }
+}
+
+/// SchedulingData is neither Clone nor Copy either
+pub fn copy_data(data: &SchedulingData) -> SchedulingData {
+    SchedulingData { ..*data }
+}
+
+/// computes the scheduling data of an item after it has been reviewed
+pub fn next_scheduling_data(data: SchedulingData, review: &UserReview) -> SchedulingData {
+    // spaced-rs adds noise of +-10% to the interval, which panics on an empty range for intervals
+    // below 10 days (i.e. every new item). Those get the same update without the noise.
+    let forgetting_rate = (1.0 / data.adjusting_factor) * (data.difficulty / data.memory_strength);
+    if compute_interval(forgetting_rate, RECALL_PROBABILITY) / 10 == 0 {
+        return next_scheduling_data_without_noise(data, review, forgetting_rate);
+    }
+    schedule(
+        data,
+        copy_review(review),
+        UpdateParameters::default(),
+        RECALL_PROBABILITY,
+    )
+}
+
+/// the update spaced_rs::schedule performs, minus the random change of the interval
+fn next_scheduling_data_without_noise(
+    data: SchedulingData,
+    review: &UserReview,
+    forgetting_rate: f32,
+) -> SchedulingData {
+    let parameters = UpdateParameters::default();
+    let difficulty = match review {
+        UserReview::TooHard => data.difficulty * parameters.difficulty_change_factor,
+        UserReview::JustEnough => data.difficulty,
+        UserReview::TooEasy => data.difficulty * (2.0 - parameters.difficulty_change_factor),
+    };
+    SchedulingData {
+        interval: compute_interval(forgetting_rate, RECALL_PROBABILITY),
+        difficulty,
+        memory_strength: data.memory_strength * parameters.memory_strength_change_factor,
+        adjusting_factor: data.adjusting_factor,
+        times_reviewed: data.times_reviewed + 1,
+        times_recalled: data.times_recalled + 1,
+    }
+}
+
+/// the timestamp an item reviewed at {now} becomes due again. We never schedule an item for the
+/// same day it was reviewed.
+pub fn due_after(now: u64, interval: i32) -> u64 {
+    now + interval.max(1) as u64 * SECONDS_PER_DAY
+}
+
+/// the days from the last review of {item} to {now}, none for items we never reviewed
+pub fn days_since_review(item: &Item, now: u64) -> f32 {
+    let last_review = item.last_review.unwrap_or(now);
+    now.saturating_sub(last_review) as f32 / SECONDS_PER_DAY as f32
+}
+
+/// the probability that we still remember an item with {data} {elapsed_days} after its last
+/// review, judging by the forgetting curve of spaced-rs
+pub fn recall_probability(data: &SchedulingData, elapsed_days: f32) -> f32 {
+    let forgetting_rate = data.difficulty / (data.memory_strength * data.adjusting_factor);
+    (-forgetting_rate * elapsed_days).exp()
+}
+
+/// how many days the due date of an item scheduled {interval} days ahead may move to balance the
+/// load: {ratio} of the interval, at least a day and at most {max_days}. Short intervals stay as
+/// they are, a day either way would change them too much.
+pub fn fuzz_days(interval: i32, ratio: f64, max_days: u32) -> u64 {
+    if interval < 3 || ratio <= 0.0 {
+        return 0;
+    }
+    ((interval as f64 * ratio).round() as u64).clamp(1, max_days as u64)
+}
+
+/// {due} moved by whole days to the day within {fuzz} days of it with the fewest items due, where
+/// {load} counts the items due on each day starting with {today}. Of equally busy days we take the
+/// one closest to {due}, the earlier one if two are as close. Nothing moves to today or before.
+pub fn balance_due(due: u64, fuzz: u64, today: u64, load: &[u32]) -> u64 {
+    let day = due / SECONDS_PER_DAY;
+    let first = day.saturating_sub(fuzz).max(today + 1);
+    let n_due = |d: u64| load.get((d - today) as usize).copied().unwrap_or(0);
+    let best = (first..=day + fuzz)
+        .min_by_key(|d| (n_due(*d), d.abs_diff(day), *d))
+        .unwrap_or(day);
+    due - day * SECONDS_PER_DAY + best * SECONDS_PER_DAY
+}
+
+/// applies a review done at {now} to the item
+pub fn review_item(item: Item, review: &UserReview, now: u64, scheduler: &dyn Scheduler) -> Item {
+    let elapsed_days = days_since_review(&item, now);
+    let (scheduling_data, scheduler_state) = scheduler.next_state(
+        &item.scheduling_data,
+        &item.scheduler_state,
+        review,
+        elapsed_days,
+    );
+    Item {
+        due: scheduler.due(&scheduling_data, now),
+        last_review: Some(now),
+        scheduling_data,
+        scheduler_state,
+        ..item
+    }
+}
+
+/// {data} after a review that led to {interval}. Only spaced-rs uses difficulty, memory strength
+/// and the adjusting factor, the other algorithms leave them be.
+fn reviewed(data: &SchedulingData, review: &UserReview, interval: i32) -> SchedulingData {
+    let recalled = !matches!(review, UserReview::TooHard);
+    SchedulingData {
+        interval: interval.max(1),
+        times_reviewed: data.times_reviewed + 1,
+        times_recalled: data.times_recalled + recalled as i32,
+        ..*data
+    }
+}
+
+impl Scheduler for SpacedRsScheduler {
+    fn initial_state(&self) -> SchedulerState {
+        SchedulerState::SpacedRs
+    }
+
+    fn next_state(
+        &self,
+        data: &SchedulingData,
+        state: &SchedulerState,
+        review: &UserReview,
+        _elapsed_days: f32,
+    ) -> (SchedulingData, SchedulerState) {
+        let mut data = copy_data(data);
+        // another algorithm moved the interval on, make the forgetting rate agree with it again
+        if *state != SchedulerState::SpacedRs && data.times_reviewed > 0 {
+            let forgetting_rate = -RECALL_PROBABILITY.ln() / data.interval.max(1) as f32;
+            data.memory_strength = data.difficulty / (data.adjusting_factor * forgetting_rate);
+        }
+        (next_scheduling_data(data, review), SchedulerState::SpacedRs)
+    }
+}
+
+/// the ease factor of a new SM-2 item and the lowest it can get
+const SM2_INITIAL_EASE: f32 = 2.5;
+const SM2_MIN_EASE: f32 = 1.3;
+
+impl Scheduler for Sm2Scheduler {
+    fn initial_state(&self) -> SchedulerState {
+        SchedulerState::Sm2 {
+            repetitions: 0,
+            ease: SM2_INITIAL_EASE,
+        }
+    }
+
+    fn next_state(
+        &self,
+        data: &SchedulingData,
+        state: &SchedulerState,
+        review: &UserReview,
+        _elapsed_days: f32,
+    ) -> (SchedulingData, SchedulerState) {
+        let (repetitions, ease) = match state {
+            SchedulerState::Sm2 { repetitions, ease } => (*repetitions, *ease),
+            _ if data.times_reviewed == 0 => (0, SM2_INITIAL_EASE),
+            // past the fixed first intervals, so the next one grows from the current one
+            _ => (2, SM2_INITIAL_EASE),
+        };
+        // the 0-5 quality of SM-2, below 3 is a lapse
+        let quality: f32 = match review {
+            UserReview::TooHard => 2.0,
+            UserReview::JustEnough => 4.0,
+            UserReview::TooEasy => 5.0,
+        };
+        let ease =
+            (ease + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(SM2_MIN_EASE);
+        let (repetitions, interval) = match (quality < 3.0, repetitions) {
+            (true, _) => (0, 1),
+            (false, 0) => (1, 1),
+            (false, 1) => (2, 6),
+            (false, n) => (n + 1, (data.interval as f32 * ease).round() as i32),
+        };
+        let state = SchedulerState::Sm2 { repetitions, ease };
+        (reviewed(data, review, interval), state)
+    }
+}
+
+/// the interval of each leitner box in days
+const LEITNER_INTERVALS: [i32; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+impl Scheduler for LeitnerScheduler {
+    fn initial_state(&self) -> SchedulerState {
+        SchedulerState::Leitner { box_index: 0 }
+    }
+
+    fn next_state(
+        &self,
+        data: &SchedulingData,
+        state: &SchedulerState,
+        review: &UserReview,
+        _elapsed_days: f32,
+    ) -> (SchedulingData, SchedulerState) {
+        let current = match state {
+            SchedulerState::Leitner { box_index } => *box_index,
+            // the last box the item's interval reaches
+            _ => LEITNER_INTERVALS
+                .iter()
+                .rposition(|interval| *interval <= data.interval)
+                .unwrap_or(0),
+        };
+        let box_index = match review {
+            UserReview::TooHard => 0,
+            UserReview::JustEnough => current + 1,
+            // easy problems skip a box
+            UserReview::TooEasy => current + 2,
+        }
+        .min(LEITNER_INTERVALS.len() - 1);
+        let state = SchedulerState::Leitner { box_index };
+        (reviewed(data, review, LEITNER_INTERVALS[box_index]), state)
+    }
+}
+
+/// the default weights of FSRS 4.5
+const FSRS_WEIGHTS: [f32; 17] = [
+    0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367, 1.0461, 2.1072,
+    0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+];
+const FSRS_DECAY: f32 = -0.5;
+// chosen so that the retrievability after {stability} days is 90%
+const FSRS_FACTOR: f32 = 19.0 / 81.0;
+/// a century, intervals beyond it are pointless
+const FSRS_MAX_INTERVAL: f32 = 36500.0;
+
+/// the probability of recalling an item {elapsed_days} after its review
+fn fsrs_retrievability(elapsed_days: f32, stability: f32) -> f32 {
+    (1.0 + FSRS_FACTOR * elapsed_days / stability).powf(FSRS_DECAY)
+}
+
+/// the difficulty of a new item after its first review
+fn fsrs_initial_difficulty(grade: f32) -> f32 {
+    let w = &FSRS_WEIGHTS;
+    (w[4] - (grade - 3.0) * w[5]).clamp(1.0, 10.0)
+}
+
+impl Scheduler for FsrsScheduler {
+    fn initial_state(&self) -> SchedulerState {
+        // a stability of 0 marks an item FSRS has not seen yet
+        SchedulerState::Fsrs {
+            stability: 0.0,
+            difficulty: 0.0,
+        }
+    }
+
+    fn next_state(
+        &self,
+        data: &SchedulingData,
+        state: &SchedulerState,
+        review: &UserReview,
+        elapsed_days: f32,
+    ) -> (SchedulingData, SchedulerState) {
+        let w = &FSRS_WEIGHTS;
+        // the 1-4 grades of FSRS: again, hard, good and easy. We have no use for hard.
+        let grade: f32 = match review {
+            UserReview::TooHard => 1.0,
+            UserReview::JustEnough => 3.0,
+            UserReview::TooEasy => 4.0,
+        };
+        let (stability, difficulty) = match state {
+            SchedulerState::Fsrs {
+                stability,
+                difficulty,
+            } => (*stability, *difficulty),
+            _ if data.times_reviewed == 0 => (0.0, 0.0),
+            // at 90% retention the interval is the stability
+            _ => (data.interval.max(1) as f32, w[4]),
+        };
+
+        let (stability, difficulty) = if stability <= 0.0 {
+            (w[grade as usize - 1], fsrs_initial_difficulty(grade))
+        } else {
+            let retrievability = fsrs_retrievability(elapsed_days.max(0.0), stability);
+            let next_difficulty = difficulty - w[6] * (grade - 3.0);
+            // drifts back towards the difficulty of a problem that was easy the first time
+            let next_difficulty = (w[7] * fsrs_initial_difficulty(4.0)
+                + (1.0 - w[7]) * next_difficulty)
+                .clamp(1.0, 10.0);
+            let next_stability = if grade == 1.0 {
+                let lapse_stability = w[11]
+                    * difficulty.powf(-w[12])
+                    * ((stability + 1.0).powf(w[13]) - 1.0)
+                    * (w[14] * (1.0 - retrievability)).exp();
+                // forgetting never makes a memory more stable
+                lapse_stability.min(stability)
+            } else {
+                let easy_bonus = if grade == 4.0 { w[16] } else { 1.0 };
+                stability
+                    * (w[8].exp()
+                        * (11.0 - difficulty)
+                        * stability.powf(-w[9])
+                        * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                        * easy_bonus
+                        + 1.0)
+            };
+            (next_stability, next_difficulty)
+        };
+
+        let interval = stability / FSRS_FACTOR * (RECALL_PROBABILITY.powf(1.0 / FSRS_DECAY) - 1.0);
+        let interval = interval.round().min(FSRS_MAX_INTERVAL) as i32;
+        let state = SchedulerState::Fsrs {
+            stability,
+            difficulty,
+        };
+        (reviewed(data, review, interval), state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn new_items_can_be_scheduled() {
+        for review in [
+            UserReview::TooHard,
+            UserReview::JustEnough,
+            UserReview::TooEasy,
+        ] {
+            let data = next_scheduling_data(SchedulingData::default(), &review);
+            assert!(data.interval >= 1);
+            assert!(data.times_reviewed == 1);
+        }
+    }
+
+    #[test]
+    fn grades_change_difficulty() {
+        let hard = next_scheduling_data(SchedulingData::default(), &UserReview::TooHard);
+        let normal = next_scheduling_data(SchedulingData::default(), &UserReview::JustEnough);
+        let easy = next_scheduling_data(SchedulingData::default(), &UserReview::TooEasy);
+        assert!(hard.difficulty > normal.difficulty);
+        assert!(normal.difficulty > easy.difficulty);
+    }
+
+    #[test]
+    fn intervals_grow_with_reviews() {
+        // reviewing over and over eventually takes us past the noise-free intervals
+        let mut data = SchedulingData::default();
+        let mut last_interval = 0;
+        for _ in 0..10 {
+            data = next_scheduling_data(data, &UserReview::TooEasy);
+            assert!(data.interval >= last_interval);
+            last_interval = data.interval;
+        }
+        assert!(last_interval >= 10);
+    }
+
+    const ALGORITHMS: [Algorithm; 4] = [
+        Algorithm::SpacedRs,
+        Algorithm::Sm2,
+        Algorithm::Leitner,
+        Algorithm::Fsrs,
+    ];
+
+    /// util function that reviews an item on the day it is due
+    fn review_on_time(
+        scheduler: &dyn Scheduler,
+        (data, state): (SchedulingData, SchedulerState),
+        review: &UserReview,
+    ) -> (SchedulingData, SchedulerState) {
+        let elapsed_days = data.interval as f32;
+        scheduler.next_state(&data, &state, review, elapsed_days)
+    }
+
+    fn random_review(rng: &mut StdRng) -> UserReview {
+        match rng.gen_range(0..3) {
+            0 => UserReview::TooHard,
+            1 => UserReview::JustEnough,
+            _ => UserReview::TooEasy,
+        }
+    }
+
+    /// util function that reviews a new item a random number of times with random grades
+    fn random_history(
+        scheduler: &dyn Scheduler,
+        rng: &mut StdRng,
+    ) -> (SchedulingData, SchedulerState) {
+        let mut item = (SchedulingData::default(), scheduler.initial_state());
+        for _ in 0..rng.gen_range(0..8) {
+            item = review_on_time(scheduler, item, &random_review(rng));
+        }
+        item
+    }
+
+    #[test]
+    fn easy_intervals_never_shrink() {
+        let mut rng = StdRng::seed_from_u64(18);
+        for algorithm in ALGORITHMS {
+            let scheduler = scheduler(algorithm);
+            for _ in 0..200 {
+                let mut item = random_history(scheduler, &mut rng);
+                let mut last_interval = if item.0.times_reviewed == 0 {
+                    0
+                } else {
+                    item.0.interval
+                };
+                for _ in 0..12 {
+                    item = review_on_time(scheduler, item, &UserReview::TooEasy);
+                    assert!(
+                        item.0.interval >= last_interval,
+                        "{algorithm:?}: {:?}",
+                        item.1
+                    );
+                    last_interval = item.0.interval;
+                }
+                // and they do grow, up to the last leitner box
+                assert!(last_interval >= 64, "{algorithm:?}: {:?}", item.1);
+            }
+        }
+    }
+
+    #[test]
+    fn every_review_schedules_into_the_future() {
+        let mut rng = StdRng::seed_from_u64(19);
+        for algorithm in ALGORITHMS {
+            let scheduler = scheduler(algorithm);
+            for _ in 0..200 {
+                let item = random_history(scheduler, &mut rng);
+                let times_reviewed = item.0.times_reviewed;
+                let (data, _) = review_on_time(scheduler, item, &random_review(&mut rng));
+                assert!(data.interval >= 1, "{algorithm:?}: {}", data.interval);
+                assert!(data.times_reviewed == times_reviewed + 1);
+                assert!(scheduler.due(&data, 1000) >= 1000 + SECONDS_PER_DAY);
+            }
+        }
+    }
+
+    #[test]
+    fn switching_algorithms_keeps_the_interval() {
+        for from in ALGORITHMS {
+            for to in ALGORITHMS {
+                let mut item = (SchedulingData::default(), scheduler(from).initial_state());
+                while item.0.interval < 30 {
+                    item = review_on_time(scheduler(from), item, &UserReview::JustEnough);
+                }
+                let interval = item.0.interval;
+                let (data, state) = review_on_time(scheduler(to), item, &UserReview::JustEnough);
+                // not back to the first day, whatever the new algorithm makes of it
+                assert!(
+                    data.interval >= interval / 2,
+                    "{from:?} -> {to:?}: {}",
+                    data.interval
+                );
+                // the state is the new algorithm's from now on
+                let initial_state = scheduler(to).initial_state();
+                assert!(std::mem::discriminant(&state) == std::mem::discriminant(&initial_state));
+            }
+        }
+    }
+
+    #[test]
+    fn lapses_shorten_the_interval() {
+        for algorithm in [Algorithm::Sm2, Algorithm::Leitner, Algorithm::Fsrs] {
+            let scheduler = scheduler(algorithm);
+            let mut item = (SchedulingData::default(), scheduler.initial_state());
+            for _ in 0..5 {
+                item = review_on_time(scheduler, item, &UserReview::JustEnough);
+            }
+            let interval = item.0.interval;
+            let (data, _) = review_on_time(scheduler, item, &UserReview::TooHard);
+            // fsrs remembers some of the stability, the others start over
+            assert!(
+                data.interval <= interval / 4,
+                "{algorithm:?}: {}",
+                data.interval
+            );
+            assert!(data.interval == 1 || algorithm == Algorithm::Fsrs);
+            assert!(data.times_recalled == 5);
+        }
+    }
+
+    #[test]
+    fn fsrs_lapses_never_add_stability() {
+        for stability in [0.5, 5.0, 100.0, 10000.0] {
+            for difficulty in [1.0, 5.0, 10.0] {
+                let data = SchedulingData {
+                    times_reviewed: 3,
+                    ..SchedulingData::default()
+                };
+                let state = SchedulerState::Fsrs {
+                    stability,
+                    difficulty,
+                };
+                // from on time to long forgotten
+                for elapsed_days in [0.0, stability, 100.0 * stability] {
+                    let (_, state) =
+                        FsrsScheduler.next_state(&data, &state, &UserReview::TooHard, elapsed_days);
+                    let SchedulerState::Fsrs {
+                        stability: next, ..
+                    } = state
+                    else {
+                        panic!("fsrs left its state: {state:?}");
+                    };
+                    assert!(next <= stability, "{stability} {difficulty}: {next}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fsrs_difficulty_reverts_to_easy() {
+        let difficulty = |(_, state): &(SchedulingData, SchedulerState)| match state {
+            SchedulerState::Fsrs { difficulty, .. } => *difficulty,
+            _ => panic!("fsrs left its state: {state:?}"),
+        };
+        let initial = (SchedulingData::default(), FsrsScheduler.initial_state());
+        let mut item = review_on_time(&FsrsScheduler, initial, &UserReview::JustEnough);
+        assert!(difficulty(&item) == fsrs_initial_difficulty(3.0));
+        // good reviews leave the difficulty alone but for the drift
+        for _ in 0..300 {
+            item = review_on_time(&FsrsScheduler, item, &UserReview::JustEnough);
+        }
+        assert!((difficulty(&item) - fsrs_initial_difficulty(4.0)).abs() < 0.01);
+    }
+
+    #[test]
+    fn fuzz_grows_with_the_interval() {
+        assert!(fuzz_days(2, 0.05, 7) == 0);
+        assert!(fuzz_days(3, 0.05, 7) == 1);
+        assert!(fuzz_days(60, 0.05, 7) == 3);
+        assert!(fuzz_days(365, 0.05, 7) == 7);
+        // turned off
+        assert!(fuzz_days(365, 0.0, 7) == 0);
+    }
+
+    #[test]
+    fn balancing_picks_the_least_busy_day() {
+        let today = 19000;
+        let due = (today + 10) * SECONDS_PER_DAY + 500;
+        let at = |day: u64| day * SECONDS_PER_DAY + 500;
+        // days 8 to 12 from today
+        let mut load = vec![0; 13];
+        load[8..].copy_from_slice(&[5, 4, 6, 4, 7]);
+        // 9 and 11 are as busy and as close, the earlier one wins
+        assert!(balance_due(due, 2, today, &load) == at(today + 9));
+        load[10] = 4;
+        assert!(balance_due(due, 2, today, &load) == at(today + 10));
+        assert!(balance_due(due, 0, today, &load) == due);
+        // never today, even if today is empty
+        let due = (today + 1) * SECONDS_PER_DAY;
+        assert!(balance_due(due, 3, today, &[0, 9, 9, 9, 1]) == (today + 4) * SECONDS_PER_DAY);
+    }
+
+    #[test]
+    fn overdue_items_are_more_forgotten() {
+        let data = SchedulingData {
+            interval: 10,
+            ..SchedulingData::default()
+        };
+        let on_time = recall_probability(&data, 10.0);
+        let late = recall_probability(&data, 15.0);
+        assert!(late < on_time && on_time < 1.0);
+        let stronger = SchedulingData {
+            memory_strength: 400.0,
+            ..data
+        };
+        assert!(recall_probability(&stronger, 10.0) > on_time);
+    }
+
+    #[test]
+    fn due_is_at_least_a_day_away() {
+        assert!(due_after(1000, 3) == 1000 + 3 * SECONDS_PER_DAY);
+        assert!(due_after(1000, 0) == 1000 + SECONDS_PER_DAY);
+    }
+}
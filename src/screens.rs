@@ -1,17 +1,23 @@
-use sm::sm;
-use tui::layout::Alignment;
-use tui::widgets::{Block, Borders, Paragraph};
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::Modifier;
+use tui::style::{Color, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{
+    BarChart, Block, Borders, List, ListItem, ListState, Paragraph, Sparkline, Wrap,
+};
 use tui::Frame;
 
-use crate::types::{Back, Term};
+use crate::constants::{BANNER_STR, FORECAST_DAYS};
+use crate::diff::{self, DiffLine};
+use crate::types::{
+    Back, InboxInputKind, JudgeReport, SessionPlan, Solution, Stats, Term, UserReview, Verdict,
+};
 use crate::AppState;
+use crate::{grading, markdown};
 
-use crate::Screen::{
-    Variant::{self as ScreenState, *},
-    *,
-};
+use crate::Screen::Variant::{self as ScreenState, *};
 
-fn str_to_paragraph(str: &str) -> Paragraph {
+fn str_to_paragraph(str: &str) -> Paragraph<'_> {
     Paragraph::new(str)
         .block(Block::default().borders(Borders::ALL))
         .alignment(Alignment::Center)
@@ -23,10 +29,538 @@ fn quick_dirty_test_screen(f: &mut Frame<Back>, state: &ScreenState) {
     f.render_widget(paragraph, f.size());
 }
 
+/// the hub of the application: how much there is to review and what we can do about it
+fn main_screen(f: &mut Frame<Back>, state: &AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    f.render_widget(str_to_paragraph(BANNER_STR), chunks[0]);
+
+    let count = |n: Option<u32>| n.map_or("-".to_string(), |n| n.to_string());
+    let mut counts = format!(
+        "due: {}\nnew: {}\ntotal: {}",
+        count(state.n_due),
+        count(state.n_new),
+        count(state.total)
+    );
+    let filter = &state.tag_filter;
+    if !filter.tags.is_empty() {
+        let matching = if filter.all { "all of" } else { "any of" };
+        counts.push_str(&format!("\ntags: {matching} {}", filter.tags.join(", ")));
+    }
+    if let Some(plan) = &state.plan {
+        counts.push_str(&format!("\n\n{}", plan_summary(plan)));
+    }
+    if let Some(message) = &state.message {
+        counts.push_str(&format!("\n\n{message}"));
+    }
+    f.render_widget(str_to_paragraph(&counts), chunks[1]);
+
+    let keys = &state.config.key_bindings;
+    let help = format!(
+        "{}/space: review | {}: undo review | {}: queue order | {}: promote new problem | {}: inbox | {}: suspended | {}: sync feeds | {}: stats | esc: quit",
+        keys.review, keys.undo_review, keys.queue_order, keys.promote_new, keys.inbox, keys.suspended, keys.sync_feeds, keys.stats
+    );
+    f.render_widget(str_to_paragraph(&help), chunks[2]);
+}
+
+/// what is left of today's reviews and what we already did
+fn plan_summary(plan: &SessionPlan) -> String {
+    let done = &plan.done;
+    let mut lines = vec![
+        format!("order: {}", plan.order.name()),
+        format!(
+            "today: {} reviews and {} new left, about {}",
+            plan.n_reviews,
+            plan.n_new,
+            format_duration(plan.estimated.as_secs())
+        ),
+        format!(
+            "done: {} reviews and {} new in {}",
+            done.reviews,
+            done.new,
+            format_duration(done.time.as_secs())
+        ),
+    ];
+    if plan.held_back > 0 {
+        lines.push(format!("{} more due after today's limits", plan.held_back));
+    }
+    lines.join("\n")
+}
+
+/// the queue of problems that are not review items yet
+fn inbox_screen(f: &mut Frame<Back>, state: &AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    let inbox = &state.inbox;
+    let entries: Vec<ListItem> = inbox
+        .entries
+        .iter()
+        .map(|entry| match entry.tags.is_empty() {
+            true => ListItem::new(entry.url.clone()),
+            false => ListItem::new(format!("{}  [{}]", entry.url, entry.tags.join(", "))),
+        })
+        .collect();
+    let list = List::new(entries)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Inbox ({})", inbox.entries.len())),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    // the list scrolls to keep the selected entry in view
+    let mut list_state = ListState::default();
+    if !inbox.entries.is_empty() {
+        list_state.select(Some(inbox.selected));
+    }
+    f.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let (input, help) = match &inbox.input {
+        Some(input) => {
+            let prompt = match input.kind {
+                InboxInputKind::Urls => "add urls",
+                InboxInputKind::Tags => "tags (-tag removes)",
+            };
+            (
+                format!("{prompt}: {}_", input.text),
+                "enter: submit | esc: close input",
+            )
+        }
+        None => (
+            state.message.clone().unwrap_or_default(),
+            "j/k: select | J/K: move down/up | g/G: move to top/bottom | p: promote | d: delete | t: tag | a: add urls | esc: back",
+        ),
+    };
+    let input = Paragraph::new(input).block(Block::default().borders(Borders::ALL));
+    f.render_widget(input, chunks[1]);
+    f.render_widget(str_to_paragraph(help), chunks[2]);
+}
+
+/// the items that are left out of reviews, to bring them back
+fn suspended_screen(f: &mut Frame<Back>, state: &AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(f.size());
+
+    let view = &state.suspended;
+    let items: Vec<ListItem> = view
+        .items
+        .iter()
+        .map(|item| ListItem::new(format!("{}  [{}]", item.url, item.status.name())))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Suspended, buried and retired ({})",
+            view.items.len()
+        )))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default();
+    if !view.items.is_empty() {
+        list_state.select(Some(view.selected));
+    }
+    f.render_stateful_widget(list, chunks[0], &mut list_state);
+
+    let keys = &state.config.key_bindings;
+    let help = format!(
+        "j/k: select | {}/enter: unsuspend | {}: suspend | {}: retire | esc: back",
+        keys.unsuspend, keys.suspend, keys.retire
+    );
+    f.render_widget(str_to_paragraph(&help), chunks[1]);
+}
+
+/// one row per weekday and one column per week, like the contribution graph on github
+fn heatmap_lines(stats: &Stats) -> Vec<Spans<'static>> {
+    const WEEKDAYS: [&str; 7] = ["mon ", "    ", "wed ", "    ", "fri ", "    ", "sun "];
+    let color = |count: u32| match count {
+        0 => Color::DarkGray,
+        1 => Color::Green,
+        2..=3 => Color::LightGreen,
+        _ => Color::White,
+    };
+    (0..7)
+        .map(|weekday| {
+            let mut spans = vec![Span::raw(WEEKDAYS[weekday])];
+            // the heatmap starts on a monday, so every 7th day is on the same row
+            spans.extend(
+                stats
+                    .reviews_per_day
+                    .iter()
+                    .skip(weekday)
+                    .step_by(7)
+                    .map(|count| Span::styled("■ ", Style::default().fg(color(*count)))),
+            );
+            Spans::from(spans)
+        })
+        .collect()
+}
+
+fn format_duration(secs: u64) -> String {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
+
+/// review history, retention and what is coming up
+fn stats_screen(f: &mut Frame<Back>, state: &AppState) {
+    let stats = match &state.stats {
+        Some(stats) => stats,
+        None => return f.render_widget(str_to_paragraph("no stats loaded"), f.size()),
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Min(6),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    let heatmap = Paragraph::new(heatmap_lines(stats))
+        .block(Block::default().borders(Borders::ALL).title("Reviews"));
+    f.render_widget(heatmap, chunks[0]);
+
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+    let last_month: Vec<u64> = stats
+        .reviews_per_day
+        .iter()
+        .rev()
+        .take(FORECAST_DAYS)
+        .rev()
+        .map(|count| *count as u64)
+        .collect();
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Reviews, last 30 days"),
+        )
+        .style(Style::default().fg(Color::Green))
+        .data(&last_month);
+    f.render_widget(sparkline, middle[0]);
+
+    let percent = |rate: f64| format!("{:.0}%", rate * 100.0);
+    let mut summary = vec![
+        format!("retention: {}", stats.retention.map_or("-".into(), percent)),
+        format!(
+            "average solve time: {}",
+            stats
+                .average_solve_time
+                .map_or("-".into(), |d| format_duration(d.as_secs()))
+        ),
+    ];
+    summary.extend(
+        stats
+            .retention_per_tag
+            .iter()
+            .map(|(tag, rate)| format!("  {tag}: {}", percent(*rate))),
+    );
+    let summary = Paragraph::new(summary.join("\n"))
+        .block(Block::default().borders(Borders::ALL).title("Retention"));
+    f.render_widget(summary, middle[1]);
+
+    // bars are labeled with the number of days from today
+    let labels: Vec<String> = (0..stats.due_forecast.len())
+        .map(|day| day.to_string())
+        .collect();
+    let forecast: Vec<(&str, u64)> = labels
+        .iter()
+        .zip(&stats.due_forecast)
+        .map(|(label, count)| (label.as_str(), *count as u64))
+        .collect();
+    let bar_chart = BarChart::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Due, next 30 days"),
+        )
+        .bar_width(2)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Yellow))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        .data(&forecast);
+    f.render_widget(bar_chart, chunks[2]);
+
+    let help = format!("esc/{}: back", state.config.key_bindings.stats);
+    f.render_widget(str_to_paragraph(&help), chunks[3]);
+}
+
+/// the latest of {solutions}, as a diff against the one before it if there is one
+fn solutions_paragraph(solutions: &[Solution]) -> Paragraph<'_> {
+    let latest = &solutions[solutions.len() - 1];
+    let (title, lines) = match solutions.len() {
+        1 => (
+            format!("Solution ({})", latest.language),
+            diff::diff_lines(&latest.source, &latest.source),
+        ),
+        n => {
+            let previous = &solutions[n - 2];
+            let title = format!(
+                "Solution {n} ({}) against solution {} ({})",
+                latest.language,
+                n - 1,
+                previous.language
+            );
+            (title, diff::diff_lines(&previous.source, &latest.source))
+        }
+    };
+    let lines: Vec<Spans> = lines
+        .into_iter()
+        .map(|line| match line {
+            DiffLine::Same(line) => Spans::from(format!("  {line}")),
+            DiffLine::Removed(line) => Spans::from(Span::styled(
+                format!("- {line}"),
+                Style::default().fg(Color::Red),
+            )),
+            DiffLine::Added(line) => Spans::from(Span::styled(
+                format!("+ {line}"),
+                Style::default().fg(Color::Green),
+            )),
+        })
+        .collect();
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// shown once the problem has been opened, before we start solving it
+fn review_info_screen(f: &mut Frame<Back>, state: &AppState) {
+    // the archived solutions get the room, if there are any
+    let constraints = match state.solutions.is_empty() {
+        true => vec![Constraint::Min(3), Constraint::Length(3)],
+        false => vec![
+            Constraint::Length(9),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ],
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(f.size());
+
+    let info = match &state.due_item {
+        Some(item) => {
+            let mut lines = vec![];
+            if let Some(metadata) = &item.metadata {
+                lines.push(metadata.title.clone().unwrap_or(metadata.name.clone()));
+                let mut details = vec![metadata.site.clone()];
+                if let Some(contest) = &metadata.contest {
+                    details.push(format!("contest {contest}"));
+                }
+                if let Some(difficulty) = &metadata.difficulty {
+                    details.push(format!("difficulty {difficulty}"));
+                }
+                lines.push(details.join(" | "));
+                lines.push(String::new());
+            }
+            lines.push(item.url.clone());
+            if !item.tags.is_empty() {
+                lines.push(format!("\ntags: {}", item.tags.join(", ")));
+            }
+            lines.join("\n")
+        }
+        None => "no item to review".to_string(),
+    };
+    f.render_widget(str_to_paragraph(&info), chunks[0]);
+    if !state.solutions.is_empty() {
+        f.render_widget(solutions_paragraph(&state.solutions), chunks[1]);
+    }
+
+    let keys = &state.config.key_bindings;
+    let help = format!(
+        "space: start timer | {}: edit notes | {}: edit samples | {}: bury until tomorrow | {}: suspend | {}: retire | esc: back",
+        keys.edit_notes, keys.edit_samples, keys.bury, keys.suspend, keys.retire
+    );
+    f.render_widget(str_to_paragraph(&help), chunks[chunks.len() - 1]);
+}
+
+/// one line per judge run: the verdict and how many samples passed, what went wrong below it
+fn judge_summary(report: &JudgeReport) -> String {
+    let passed = report
+        .results
+        .iter()
+        .filter(|verdict| **verdict == Verdict::Accepted)
+        .count();
+    let file_name = report.solution.file_name().unwrap_or_default();
+    let mut summary = format!(
+        "{}: {} ({passed}/{} samples)",
+        file_name.to_string_lossy(),
+        report.verdict.code(),
+        report.results.len()
+    );
+    if !report.details.is_empty() {
+        summary.push_str(&format!("\n{}", report.details));
+    }
+    summary
+}
+
+/// shown while we are solving the problem
+fn review_timer_screen(f: &mut Frame<Back>, state: &AppState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(f.size());
+
+    let mut timer = if state.config.timer.show_elapsed {
+        let elapsed = format_duration(state.time_stamp.map_or(0, |t| t.elapsed().as_secs()));
+        match &state.due_item {
+            Some(item) => {
+                let target = grading::target_time(item, &state.config.grading);
+                format!("{elapsed} of {}", format_duration(target.as_secs()))
+            }
+            None => elapsed,
+        }
+    } else {
+        "solving...".to_string()
+    };
+    if let Some(report) = &state.judge_report {
+        timer.push_str(&format!("\n\n{}", judge_summary(report)));
+    }
+    if let Some(message) = &state.message {
+        timer.push_str(&format!("\n\n{message}"));
+    }
+    f.render_widget(
+        str_to_paragraph(&timer).wrap(Wrap { trim: false }),
+        chunks[0],
+    );
+
+    let help = format!(
+        "space: solved | {}: run samples | esc: cancel timer",
+        state.config.key_bindings.run_judge
+    );
+    f.render_widget(str_to_paragraph(&help), chunks[1]);
+}
+
+/// shown after the timer is stopped: the problem, its notes and how to grade the attempt
+fn review_eval_screen(f: &mut Frame<Back>, state: &AppState) {
+    let (url, notes) = match &state.due_item {
+        Some(item) => (item.url.as_str(), item.notes.as_str()),
+        None => ("", ""),
+    };
+    let mut header = vec![url.to_string()];
+    let mut attempt = vec![];
+    if let Some(duration) = state.duration {
+        let target = state
+            .due_item
+            .as_ref()
+            .map(|item| grading::target_time(item, &state.config.grading));
+        attempt.push(format!(
+            "solved in {} of {}",
+            format_duration(duration.as_secs()),
+            format_duration(target.unwrap_or_default().as_secs())
+        ));
+    }
+    if let Some(report) = &state.judge_report {
+        attempt.push(format!("judge: {}", report.verdict.code()));
+    }
+    if !attempt.is_empty() {
+        header.push(attempt.join(" | "));
+    }
+    if let Some(message) = &state.message {
+        header.push(message.clone());
+    }
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(header.len() as u16 + 2),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ])
+        .split(f.size());
+
+    f.render_widget(str_to_paragraph(&header.join("\n")), chunks[0]);
+
+    let keys = &state.config.key_bindings;
+    let notes = if notes.trim().is_empty() {
+        markdown::render(&format!(
+            "no notes yet, press {} to write some",
+            keys.edit_notes
+        ))
+    } else {
+        markdown::render(notes)
+    };
+    let notes_paragraph = Paragraph::new(notes)
+        .block(Block::default().borders(Borders::ALL).title("Notes"))
+        .wrap(Wrap { trim: false });
+    f.render_widget(notes_paragraph, chunks[1]);
+
+    // the suggested grade is selected to begin with
+    let selected = state
+        .selected_grade
+        .as_ref()
+        .unwrap_or(&UserReview::JustEnough);
+    let mut help = vec![];
+    for (key, grade) in [
+        (keys.easy, UserReview::TooEasy),
+        (keys.normal, UserReview::JustEnough),
+        (keys.hard, UserReview::TooHard),
+    ] {
+        let style = match grading::grade_name(&grade) == grading::grade_name(selected) {
+            true => Style::default().add_modifier(Modifier::REVERSED),
+            false => Style::default(),
+        };
+        help.push(Span::styled(
+            format!("{key}: {}", grading::grade_name(&grade)),
+            style,
+        ));
+        help.push(Span::raw(" | "));
+    }
+    help.push(Span::raw(format!(
+        "space: grade selected | left/right: select | {}: edit notes | {}: save solution | esc: restart timer",
+        keys.edit_notes, keys.attach_solution
+    )));
+    let help_paragraph = Paragraph::new(Spans::from(help))
+        .block(Block::default().borders(Borders::ALL))
+        .alignment(Alignment::Center);
+    f.render_widget(help_paragraph, chunks[2]);
+}
+
 pub fn ui(term: &mut Term, state: &AppState) -> std::io::Result<()> {
     let screen_state: &ScreenState = &state.screen_state;
-    term.draw(|f| {
-        quick_dirty_test_screen(f, screen_state);
+    term.draw(|f| match screen_state {
+        MainScreenByStartApp(_)
+        | MainScreenByPromoteNewToDue(_)
+        | MainScreenBySyncFeeds(_)
+        | MainScreenByCloseInbox(_)
+        | MainScreenByCancelReview(_)
+        | MainScreenByReviewEasy(_)
+        | MainScreenByReviewNormal(_)
+        | MainScreenByReviewHard(_)
+        | MainScreenByUndoReview(_)
+        | MainScreenBySetStatus(_)
+        | MainScreenByCloseSuspended(_)
+        | MainScreenByCycleOrder(_)
+        | MainScreenByCloseStats(_) => main_screen(f, state),
+        StatsScreenByShowStats(_) => stats_screen(f, state),
+        InboxScreenByShowInbox(_) | InboxScreenByEditInbox(_) => inbox_screen(f, state),
+        SuspendedScreenByShowSuspended(_) | SuspendedScreenByEditSuspended(_) => {
+            suspended_screen(f, state)
+        }
+        ReviewInfoScreenByStartReview(_)
+        | ReviewInfoScreenByCancelTimer(_)
+        | ReviewInfoScreenByEditNotes(_)
+        | ReviewInfoScreenByEditSamples(_) => review_info_screen(f, state),
+        ReviewTimerScreenByStartTimer(_)
+        | ReviewTimerScreenByRestartTimer(_)
+        | ReviewTimerScreenByRunJudge(_) => review_timer_screen(f, state),
+        ReviewEvalScreenByStopTimer(_)
+        | ReviewEvalScreenByEditNotes(_)
+        | ReviewEvalScreenByAttachSolution(_) => review_eval_screen(f, state),
+        _ => quick_dirty_test_screen(f, screen_state),
     })?;
     Ok(())
 }
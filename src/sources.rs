@@ -0,0 +1,329 @@
+//! This module contains the problem sources: the sites we know how to read problem metadata from.
+//! Everything is read off the url, and optionally off a copy of the problem page that was saved
+//! to the page cache. We never download anything ourselves.
+
+// stdlib imports
+use std::path::Path;
+// external imports
+use url::Url;
+// internal imports
+use crate::{types::ProblemMetadata, urls::canonicalize_url};
+
+/// A site that hosts problems
+pub trait ProblemSource {
+    /// short lowercase name of the site, also the directory of its pages in the page cache
+    fn site(&self) -> &'static str;
+
+    /// the metadata that can be read off {url}, None if the url is not a problem of this site
+    fn parse_url(&self, url: &Url) -> Option<ProblemMetadata>;
+
+    /// fills in the title and difficulty found in {html}, a saved copy of the problem page
+    fn enrich(&self, html: &str, metadata: &mut ProblemMetadata);
+}
+
+pub struct Kattis;
+pub struct Codeforces;
+pub struct LeetCode;
+pub struct AtCoder;
+
+/// every source we know, tried in order
+pub const SOURCES: &[&dyn ProblemSource] = &[&Kattis, &Codeforces, &LeetCode, &AtCoder];
+
+/// the metadata of the problem behind {url}, None for urls of sites we don't know
+pub fn problem_metadata(url: &str) -> Option<ProblemMetadata> {
+    let url = Url::parse(&canonicalize_url(url)).ok()?;
+    SOURCES.iter().find_map(|source| source.parse_url(&url))
+}
+
+/// like [problem_metadata], enriched from {cache_dir}/{site}/{problem_id}.html if that page
+/// was saved
+pub fn problem_metadata_with_cache(url: &str, cache_dir: &Path) -> Option<ProblemMetadata> {
+    let mut metadata = problem_metadata(url)?;
+    let source = SOURCES
+        .iter()
+        .find(|source| source.site() == metadata.site)?;
+    let page = cache_dir
+        .join(&metadata.site)
+        .join(format!("{}.html", metadata.problem_id));
+    // a missing or unreadable page only means we have less to show
+    if let Ok(html) = std::fs::read_to_string(page) {
+        source.enrich(&html, &mut metadata);
+    }
+    Some(metadata)
+}
+
+/// the non-empty path segments of {url}
+fn segments(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// the text in {html} between the first {start} and the {end} after it
+fn between<'a>(html: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let from = html.find(start)? + start.len();
+    let to = html[from..].find(end)? + from;
+    Some(&html[from..to])
+}
+
+/// {fragment} without tags, entities decoded and whitespace collapsed. None if nothing is left.
+fn text_of(fragment: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in fragment.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&ndash;", "–")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+impl ProblemSource for Kattis {
+    fn site(&self) -> &'static str {
+        "kattis"
+    }
+
+    fn parse_url(&self, url: &Url) -> Option<ProblemMetadata> {
+        if url.host_str()? != "open.kattis.com" {
+            return None;
+        }
+        let (contest, problem_id) = match segments(url)[..] {
+            ["problems", id, ..] => (None, id),
+            ["contests", contest, "problems", id, ..] => (Some(contest.to_string()), id),
+            _ => return None,
+        };
+        Some(ProblemMetadata::new(
+            self.site(),
+            problem_id,
+            contest,
+            problem_id,
+        ))
+    }
+
+    fn enrich(&self, html: &str, metadata: &mut ProblemMetadata) {
+        metadata.title = between(html, "<h1", "</h1>")
+            .and_then(|h1| h1.split_once('>'))
+            .and_then(|(_, title)| text_of(title))
+            .or(metadata.title.take());
+        metadata.difficulty = between(html, "class=\"difficulty_number\">", "<")
+            .and_then(text_of)
+            .or(metadata.difficulty.take());
+    }
+}
+
+impl ProblemSource for Codeforces {
+    fn site(&self) -> &'static str {
+        "codeforces"
+    }
+
+    fn parse_url(&self, url: &Url) -> Option<ProblemMetadata> {
+        if url.host_str()? != "codeforces.com" {
+            return None;
+        }
+        let (contest, index) = match segments(url)[..] {
+            ["problemset", "problem", contest, index, ..] => (contest, index),
+            ["contest" | "gym", contest, "problem", index, ..] => (contest, index),
+            _ => return None,
+        };
+        let problem_id = format!("{contest}{}", index.to_uppercase());
+        Some(ProblemMetadata::new(
+            self.site(),
+            &problem_id,
+            Some(contest.to_string()),
+            &problem_id,
+        ))
+    }
+
+    fn enrich(&self, html: &str, metadata: &mut ProblemMetadata) {
+        metadata.title = between(html, "<div class=\"title\">", "</div>")
+            .and_then(text_of)
+            .or(metadata.title.take());
+        // problems are rated like "*1600" among the tags
+        metadata.difficulty = between(html, "title=\"Difficulty\">", "<")
+            .and_then(text_of)
+            .map(|rating| rating.trim_start_matches('*').to_string())
+            .or(metadata.difficulty.take());
+    }
+}
+
+impl ProblemSource for LeetCode {
+    fn site(&self) -> &'static str {
+        "leetcode"
+    }
+
+    fn parse_url(&self, url: &Url) -> Option<ProblemMetadata> {
+        if url.host_str()? != "leetcode.com" {
+            return None;
+        }
+        let slug = match segments(url)[..] {
+            ["problems", slug, ..] => slug,
+            _ => return None,
+        };
+        // two-sum is called "Two Sum"
+        let name = slug
+            .split('-')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or(String::new(), |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(ProblemMetadata::new(self.site(), slug, None, &name))
+    }
+
+    fn enrich(&self, html: &str, metadata: &mut ProblemMetadata) {
+        metadata.title = between(html, "<title>", "</title>")
+            .and_then(text_of)
+            .map(|title| title.trim_end_matches(" - LeetCode").to_string())
+            .or(metadata.title.take());
+        // the page is rendered by javascript, but the question data is embedded as json
+        metadata.difficulty = between(html, "\"difficulty\":\"", "\"")
+            .and_then(text_of)
+            .or(metadata.difficulty.take());
+    }
+}
+
+impl ProblemSource for AtCoder {
+    fn site(&self) -> &'static str {
+        "atcoder"
+    }
+
+    fn parse_url(&self, url: &Url) -> Option<ProblemMetadata> {
+        if url.host_str()? != "atcoder.jp" {
+            return None;
+        }
+        let (contest, task) = match segments(url)[..] {
+            ["contests", contest, "tasks", task, ..] => (contest, task),
+            _ => return None,
+        };
+        // abc300_a is problem A of ABC300
+        let name = match task.rsplit_once('_') {
+            Some((_, letter)) => format!("{} {}", contest.to_uppercase(), letter.to_uppercase()),
+            None => task.to_string(),
+        };
+        Some(ProblemMetadata::new(
+            self.site(),
+            task,
+            Some(contest.to_string()),
+            &name,
+        ))
+    }
+
+    fn enrich(&self, html: &str, metadata: &mut ProblemMetadata) {
+        // task pages don't show a difficulty
+        metadata.title = between(html, "<span class=\"h2\">", "</span>")
+            .and_then(|title| title.split('<').next())
+            .and_then(text_of)
+            .or(metadata.title.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::create_temp_dir;
+    use serial_test::serial;
+
+    #[test]
+    fn kattis_urls() {
+        let metadata = problem_metadata("https://open.kattis.com/problems/hello").unwrap();
+        assert!(metadata == ProblemMetadata::new("kattis", "hello", None, "hello"));
+        // aliases are canonicalized first
+        let metadata = problem_metadata("http://kattis.com/contests/nwerc22/problems/faktor");
+        let metadata = metadata.unwrap();
+        assert!(metadata.problem_id == "faktor");
+        assert!(metadata.contest.as_deref() == Some("nwerc22"));
+        assert!(problem_metadata("https://open.kattis.com/help").is_none());
+    }
+
+    #[test]
+    fn codeforces_urls() {
+        for url in [
+            "https://codeforces.com/problemset/problem/4/A",
+            "https://codeforces.com/contest/4/problem/a",
+            "https://m1.codeforces.com/contest/4/problem/A",
+        ] {
+            let metadata = problem_metadata(url).unwrap();
+            assert!(metadata == ProblemMetadata::new("codeforces", "4A", Some("4".into()), "4A"));
+        }
+        assert!(problem_metadata("https://codeforces.com/blog/entry/1").is_none());
+    }
+
+    #[test]
+    fn leetcode_and_atcoder_urls() {
+        let metadata = problem_metadata("https://leetcode.com/problems/two-sum/description/");
+        let metadata = metadata.unwrap();
+        assert!(metadata == ProblemMetadata::new("leetcode", "two-sum", None, "Two Sum"));
+        let metadata = problem_metadata("https://atcoder.jp/contests/abc300/tasks/abc300_a");
+        let metadata = metadata.unwrap();
+        assert!(metadata.problem_id == "abc300_a");
+        assert!(metadata.contest.as_deref() == Some("abc300"));
+        assert!(metadata.name == "ABC300 A");
+        assert!(problem_metadata("https://example.com/problems/hello").is_none());
+        assert!(problem_metadata("not a url").is_none());
+    }
+
+    #[test]
+    #[serial]
+    fn enrich_from_cached_pages() {
+        let (db_path, cleanup) = create_temp_dir("enrich_from_cached_pages");
+        let cache_dir = db_path.with_file_name("pages");
+        let pages = [
+            (
+                "kattis/hello.html",
+                "<h1 class=\"book-page-heading\">Hello World!</h1>\
+                 <span class=\"difficulty_number\">1.2</span>",
+            ),
+            (
+                "codeforces/4A.html",
+                "<div class=\"header\"><div class=\"title\">A. Watermelon</div></div>\
+                 <span class=\"tag-box\" title=\"Difficulty\">\n  *800\n</span>",
+            ),
+            (
+                "leetcode/two-sum.html",
+                "<title>Two Sum - LeetCode</title>{\"difficulty\":\"Easy\"}",
+            ),
+            (
+                "atcoder/abc300_a.html",
+                "<span class=\"h2\">A - N-choice question <a href=\"#\">Editorial</a></span>",
+            ),
+        ];
+        for (path, html) in pages {
+            let path = cache_dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, html).unwrap();
+        }
+        let enriched = |url: &str| problem_metadata_with_cache(url, &cache_dir).unwrap();
+
+        let kattis = enriched("https://open.kattis.com/problems/hello");
+        assert!(kattis.title.as_deref() == Some("Hello World!"));
+        assert!(kattis.difficulty.as_deref() == Some("1.2"));
+        let codeforces = enriched("https://codeforces.com/problemset/problem/4/A");
+        assert!(codeforces.title.as_deref() == Some("A. Watermelon"));
+        assert!(codeforces.difficulty.as_deref() == Some("800"));
+        let leetcode = enriched("https://leetcode.com/problems/two-sum");
+        assert!(leetcode.title.as_deref() == Some("Two Sum"));
+        assert!(leetcode.difficulty.as_deref() == Some("Easy"));
+        let atcoder = enriched("https://atcoder.jp/contests/abc300/tasks/abc300_a");
+        assert!(atcoder.title.as_deref() == Some("A - N-choice question"));
+        assert!(atcoder.difficulty.is_none());
+        // no page saved, only what the url tells us
+        let faktor = enriched("https://open.kattis.com/problems/faktor");
+        assert!(faktor.title.is_none());
+        assert!(cleanup().is_ok());
+    }
+}
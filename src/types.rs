@@ -1,14 +1,43 @@
-use sm::sm;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tui::{backend::CrosstermBackend, Terminal};
 
-pub use spaced_rs::SchedulingData;
+use crate::planner::QueueOrder;
+
+pub use spaced_rs::{SchedulingData, UserReview};
 
 pub type SchemaVersion = usize;
 pub type ItemId = u64;
+pub type ReviewId = u64;
+pub type FeedId = u64;
 
 // Item specific types
 
+/// Whether an item takes part in reviews
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ItemStatus {
+    #[default]
+    Active,
+    // left out of reviews until it is made active again
+    Suspended,
+    // left out of reviews until the timestamp, the start of tomorrow when buried during a review
+    Buried(u64),
+    // mastered, there is no need to review it anymore
+    Retired,
+}
+
+impl ItemStatus {
+    /// how the status is shown, also how it is stored
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemStatus::Active => "active",
+            ItemStatus::Suspended => "suspended",
+            ItemStatus::Buried(_) => "buried",
+            ItemStatus::Retired => "retired",
+        }
+    }
+}
+
 /// The review item type.
 pub struct Item {
     // the first time we insert the item into the item table we want it to provide us with a id
@@ -18,9 +47,173 @@ pub struct Item {
     pub scheduling_data: SchedulingData,
     // timestamp
     pub due: u64,
+    // timestamp of the last review, None for items we never reviewed. Spreading the backlog moves
+    // the due date, so we can't tell the last review from it.
+    pub last_review: Option<u64>,
 
     // url to problem
     pub url: String,
+
+    // tags such as "graphs" or "dp", stored in the tags table
+    pub tags: Vec<String>,
+
+    // markdown notes shown after solving the problem
+    pub notes: String,
+
+    // what we know about the problem besides its url, None for sites we don't know
+    pub metadata: Option<ProblemMetadata>,
+
+    // what the algorithm that scheduled the item last remembers about it, besides scheduling_data
+    pub scheduler_state: SchedulerState,
+
+    // how long we want to take to solve the problem, None to go by its difficulty
+    pub target: Option<Duration>,
+}
+
+/// The state each scheduling algorithm keeps per item, stored as json in the items table
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(tag = "algorithm", rename_all = "kebab-case")]
+pub enum SchedulerState {
+    // spaced-rs keeps its state in the scheduling data, items from before there was a choice of
+    // algorithm have this state too
+    #[default]
+    SpacedRs,
+    Sm2 {
+        // successful reviews in a row
+        repetitions: u32,
+        ease: f32,
+    },
+    Leitner {
+        box_index: usize,
+    },
+    Fsrs {
+        // days until the recall probability drops to 90%, 0 before the first review
+        stability: f32,
+        difficulty: f32,
+    },
+}
+
+/// What a problem source could tell us about a problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemMetadata {
+    // the site the problem is from, e.g. "kattis"
+    pub site: String,
+    // the id of the problem on its site, e.g. "hello" or "4A"
+    pub problem_id: String,
+    pub contest: Option<String>,
+    // a readable name derived from the url
+    pub name: String,
+    // title and difficulty are only known once the problem page was saved to the page cache
+    pub title: Option<String>,
+    pub difficulty: Option<String>,
+}
+
+impl ProblemMetadata {
+    pub fn new(site: &str, problem_id: &str, contest: Option<String>, name: &str) -> Self {
+        Self {
+            site: site.into(),
+            problem_id: problem_id.into(),
+            contest,
+            name: name.into(),
+            title: None,
+            difficulty: None,
+        }
+    }
+}
+
+/// A saved version of an item's notes
+pub struct NoteRevision {
+    pub notes: String,
+    pub timestamp: u64,
+}
+
+/// A graded review of an item, as stored in the review log
+pub struct ReviewLog {
+    pub id: ReviewId,
+    pub item_id: ItemId,
+    // when the item was graded
+    pub timestamp: u64,
+    pub grade: UserReview,
+    // time spent solving, None if the timer was not used
+    pub duration: Option<Duration>,
+    pub interval_before: i32,
+    pub interval_after: i32,
+    pub difficulty_before: f32,
+    pub difficulty_after: f32,
+    // what the local judge said about the solution, None if it was not run
+    pub verdict: Option<Verdict>,
+}
+
+/// An item as it was before a review we may still want to take back, together with the log entry
+/// of that review
+pub struct ReviewUndo {
+    pub item: Item,
+    pub review_id: ReviewId,
+}
+
+/// A sample input and the output it should produce, used by the local judge
+pub struct SampleTest {
+    pub input: String,
+    pub output: String,
+}
+
+/// A solution we wrote for an item, kept so we can see how our approach evolved
+pub struct Solution {
+    pub id: u64,
+    pub item_id: ItemId,
+    // the review it was written for, None if it was archived outside a review
+    pub review_id: Option<ReviewId>,
+    // the file extension of the source, e.g. "cpp"
+    pub language: String,
+    pub source: String,
+    pub timestamp: u64,
+}
+
+/// The outcome of judging a solution, for a single sample or for all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Accepted,
+    WrongAnswer,
+    TimeLimitExceeded,
+    RuntimeError,
+    CompileError,
+}
+
+impl Verdict {
+    /// the abbreviation judges use, also how verdicts are stored
+    pub fn code(&self) -> &'static str {
+        match self {
+            Verdict::Accepted => "AC",
+            Verdict::WrongAnswer => "WA",
+            Verdict::TimeLimitExceeded => "TLE",
+            Verdict::RuntimeError => "RE",
+            Verdict::CompileError => "CE",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        [
+            Verdict::Accepted,
+            Verdict::WrongAnswer,
+            Verdict::TimeLimitExceeded,
+            Verdict::RuntimeError,
+            Verdict::CompileError,
+        ]
+        .into_iter()
+        .find(|verdict| verdict.code() == code)
+    }
+}
+
+/// The verdicts of one run of the local judge
+pub struct JudgeReport {
+    // the solution file that was judged
+    pub solution: std::path::PathBuf,
+    // Accepted if every sample was, the verdict of the first failing sample otherwise
+    pub verdict: Verdict,
+    // one verdict per sample, empty if the solution did not compile
+    pub results: Vec<Verdict>,
+    // compiler output or stderr of the first failing sample, to show what went wrong
+    pub details: String,
 }
 
 /// Struct representing problem resource that has not been made into a review item.
@@ -28,19 +221,134 @@ pub struct Item {
 pub struct URLItem {
     pub id: u64,
     pub url: String,
+    pub tags: Vec<String>,
 }
 
-pub struct AppConfig {
-    pub db_path: PathBuf,
+/// An inbox entry the daily drip promoted
+pub struct DripPromotion {
+    // when the drip ran
+    pub timestamp: u64,
+    // None once the item is deleted
+    pub item_id: Option<ItemId>,
+    pub url: String,
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            db_path: "./database/test.db".into(),
-        }
-    }
+/// What the text input line of the inbox screen is typing
+pub enum InboxInputKind {
+    // whitespace separated urls to add to the inbox
+    Urls,
+    // tags for the selected entry, tags starting with '-' are removed
+    Tags,
+}
+
+pub struct InboxInput {
+    pub kind: InboxInputKind,
+    pub text: String,
+}
+
+/// A change to the inbox asked for on the inbox screen
+pub enum InboxAction {
+    // move the entry with the given id to the given index of the queue
+    Move(u64, usize),
+    Delete(u64),
+    Promote(u64),
+    AddUrls(String),
+    EditTags(u64, String),
+}
+
+/// The state of the inbox screen
+#[derive(Default)]
+pub struct InboxView {
+    // the whole inbox in queue order
+    pub entries: Vec<URLItem>,
+    // index into entries
+    pub selected: usize,
+    pub input: Option<InboxInput>,
+    // set when a key press asks for a change, perform_action applies it
+    pub action: Option<InboxAction>,
+}
+
+/// An item that is left out of reviews, as listed on the suspended items screen
+pub struct SuspendedItem {
+    pub id: ItemId,
+    pub url: String,
+    pub status: ItemStatus,
+}
+
+/// The state of the suspended items screen
+#[derive(Default)]
+pub struct SuspendedView {
+    // suspended, buried and retired items by id
+    pub items: Vec<SuspendedItem>,
+    // index into items
+    pub selected: usize,
+    // set when a key press asks for a new status, perform_action applies it
+    pub action: Option<(ItemId, ItemStatus)>,
+}
+
+/// What became of the urls we tried to add to the inbox, in their canonical form
+#[derive(Default)]
+pub struct InboxReport {
+    pub added: Vec<String>,
+    pub already_in_inbox: Vec<String>,
+    pub already_items: Vec<String>,
+}
+
+/// An rss or atom feed we pull new problems from
+pub struct Feed {
+    pub id: FeedId,
+    // an http(s) url or the path of a local file
+    pub url: String,
+    // None until the first successful sync
+    pub last_synced: Option<u64>,
 }
+
+/// A single entry of a feed. The guid identifies the entry within its feed, so we can tell which
+/// entries we have already added to the inbox.
+pub struct FeedEntry {
+    pub guid: String,
+    pub link: String,
+}
+
+/// Aggregates shown on the stats screen. Days are counted in whole UTC days since the unix epoch.
+pub struct Stats {
+    // the first day of the heatmap, always a monday
+    pub heatmap_start_day: u64,
+    // number of reviews per day, starting at heatmap_start_day and ending today
+    pub reviews_per_day: Vec<u32>,
+    // times_recalled / times_reviewed over all items, None before the first review
+    pub retention: Option<f64>,
+    // the same ratio per tag, for tags with reviewed items
+    pub retention_per_tag: Vec<(String, f64)>,
+    // number of items due on each of the coming days, overdue items count towards today
+    pub due_forecast: Vec<u32>,
+    // average time spent on reviews that were timed
+    pub average_solve_time: Option<Duration>,
+}
+
+/// What we reviewed so far today
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DoneToday {
+    // reviews of items we had solved before
+    pub reviews: u32,
+    // first reviews of new items
+    pub new: u32,
+    // time spent on the reviews that were timed
+    pub time: Duration,
+}
+
+/// What is left of today's reviews, the queue itself is the due queue of the app
+pub struct SessionPlan {
+    pub n_reviews: usize,
+    pub n_new: usize,
+    // how long the queue will probably take
+    pub estimated: Duration,
+    // due items that did not fit into today's limits
+    pub held_back: usize,
+    pub done: DoneToday,
+    pub order: QueueOrder,
+}
+
 // Item specific type end here
 
 // typealiases for tui and crossterm specific types
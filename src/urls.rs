@@ -0,0 +1,103 @@
+//! This module contains the url canonicalizer. The same problem is often linked in slightly
+//! different ways, canonical urls let us recognise it before it ends up in the inbox twice.
+
+// external imports
+use url::Url;
+
+/// query parameters that only tell a site where we came from
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "mc_cid", "mc_eid", "ref", "source"];
+
+/// hosts that serve the same problems as another host, paired with the host we keep
+const HOST_ALIASES: &[(&str, &str)] = &[
+    ("kattis.com", "open.kattis.com"),
+    ("www.kattis.com", "open.kattis.com"),
+    ("www.codeforces.com", "codeforces.com"),
+    ("m1.codeforces.com", "codeforces.com"),
+    ("m2.codeforces.com", "codeforces.com"),
+    ("m3.codeforces.com", "codeforces.com"),
+    ("www.leetcode.com", "leetcode.com"),
+    ("www.atcoder.jp", "atcoder.jp"),
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+/// the canonical form of {url}: https, the preferred alias of the host, no tracking parameters and
+/// no trailing slash. Anything that does not parse as an http(s) url is only trimmed.
+pub fn canonicalize_url(url: &str) -> String {
+    let url = url.trim();
+    let mut parsed = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        _ => return url.to_string(),
+    };
+    // both are special schemes, so switching between them can't fail
+    let _ = parsed.set_scheme("https");
+    if let Some((_, host)) = HOST_ALIASES
+        .iter()
+        .find(|(alias, _)| parsed.host_str() == Some(alias))
+    {
+        // the aliases are valid hosts
+        let _ = parsed.set_host(Some(host));
+    }
+    let query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(query);
+    }
+    let path = parsed.path().trim_end_matches('/').to_string();
+    parsed.set_path(&path);
+
+    // the root path is serialized as a slash even when it is empty
+    let canonical = parsed.to_string();
+    match (parsed.path(), parsed.query(), parsed.fragment()) {
+        ("/", None, None) => canonical.trim_end_matches('/').to_string(),
+        _ => canonical,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_urls_are_unchanged() {
+        let url = "https://open.kattis.com/problems/hello";
+        assert!(canonicalize_url(url) == url);
+        let url = "https://codeforces.com/problemset/problem/4/A";
+        assert!(canonicalize_url(url) == url);
+    }
+
+    #[test]
+    fn variations_of_the_same_problem() {
+        let canonical = "https://open.kattis.com/problems/hello";
+        for url in [
+            "http://open.kattis.com/problems/hello",
+            "https://kattis.com/problems/hello/",
+            "  https://OPEN.kattis.com/problems/hello  ",
+            "https://open.kattis.com/problems/hello?utm_source=rss&utm_medium=feed",
+            "http://www.kattis.com/problems/hello/?fbclid=abc",
+        ] {
+            assert!(canonicalize_url(url) == canonical);
+        }
+        assert!(canonicalize_url("https://kattis.com/") == "https://open.kattis.com");
+    }
+
+    #[test]
+    fn meaningful_parts_are_kept() {
+        let url = "https://leetcode.com/problems/two-sum?envType=study-plan&utm_campaign=x";
+        assert!(
+            canonicalize_url(url) == "https://leetcode.com/problems/two-sum?envType=study-plan"
+        );
+        let url = "https://atcoder.jp/contests/abc300/tasks/abc300_a#statement";
+        assert!(canonicalize_url(url) == url);
+        // not an http url, we don't know how to canonicalize it
+        assert!(canonicalize_url(" not a url ") == "not a url");
+        assert!(canonicalize_url("file:///tmp/problem.pdf") == "file:///tmp/problem.pdf");
+    }
+}
@@ -0,0 +1,21 @@
+-- schema version 1: items and inbox tables
+CREATE TABLE items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    interval INTEGER NOT NULL,
+    difficulty REAL NOT NULL,
+    memory_strength REAL NOT NULL,
+    adjusting_factor REAL NOT NULL,
+    times_reviewed INTEGER NOT NULL,
+    times_recalled INTEGER NOT NULL,
+    due INTEGER NOT NULL,
+    url TEXT NOT NULL UNIQUE
+);
+CREATE TABLE inbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL
+);
+INSERT INTO items (interval, difficulty, memory_strength, adjusting_factor, times_reviewed, times_recalled, due, url)
+VALUES (1, 10.0, 100.0, 1.0, 0, 0, 1654000000, 'https://open.kattis.com/problems/hello'),
+       (4, 11.0, 160.0, 1.0, 1, 1, 1654345600, 'https://open.kattis.com/problems/faktor');
+INSERT INTO inbox (url) VALUES ('https://open.kattis.com/problems/autori');
+PRAGMA user_version = 1;
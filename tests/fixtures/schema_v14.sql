@@ -0,0 +1,121 @@
+-- schema version 4: review log
+CREATE TABLE items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    interval INTEGER NOT NULL,
+    difficulty REAL NOT NULL,
+    memory_strength REAL NOT NULL,
+    adjusting_factor REAL NOT NULL,
+    times_reviewed INTEGER NOT NULL,
+    times_recalled INTEGER NOT NULL,
+    due INTEGER NOT NULL,
+    url TEXT NOT NULL UNIQUE
+);
+CREATE TABLE inbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL
+);
+INSERT INTO items (interval, difficulty, memory_strength, adjusting_factor, times_reviewed, times_recalled, due, url)
+VALUES (1, 10.0, 100.0, 1.0, 0, 0, 1654000000, 'https://open.kattis.com/problems/hello'),
+       (4, 11.0, 160.0, 1.0, 1, 1, 1654345600, 'https://open.kattis.com/problems/faktor');
+INSERT INTO inbox (url) VALUES ('https://open.kattis.com/problems/autori');
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE item_tags (
+    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (item_id, tag_id)
+);
+CREATE TABLE inbox_tags (
+    inbox_id INTEGER NOT NULL REFERENCES inbox(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    PRIMARY KEY (inbox_id, tag_id)
+);
+INSERT INTO tags (name) VALUES ('math'), ('strings');
+INSERT INTO item_tags (item_id, tag_id) VALUES (2, 1);
+INSERT INTO inbox_tags (inbox_id, tag_id) VALUES (1, 2);
+ALTER TABLE items ADD COLUMN item_notes TEXT NOT NULL DEFAULT '';
+CREATE TABLE note_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    notes TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+UPDATE items SET item_notes = '# faktor' WHERE id = 2;
+INSERT INTO note_history (item_id, notes, timestamp) VALUES (2, '# faktor', 1654345600);
+CREATE TABLE reviews (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    timestamp INTEGER NOT NULL,
+    grade INTEGER NOT NULL,
+    duration INTEGER,
+    interval_before INTEGER NOT NULL,
+    interval_after INTEGER NOT NULL,
+    difficulty_before REAL NOT NULL,
+    difficulty_after REAL NOT NULL
+);
+CREATE INDEX reviews_item_id ON reviews (item_id);
+INSERT INTO reviews (item_id, timestamp, grade, duration, interval_before, interval_after, difficulty_before, difficulty_after)
+VALUES (2, 1654000000, 2, 754000, 1, 4, 10.0, 11.0);
+CREATE TABLE feeds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL UNIQUE,
+    last_synced INTEGER
+);
+CREATE TABLE feed_entries (
+    feed_id INTEGER NOT NULL REFERENCES feeds(id) ON DELETE CASCADE,
+    guid TEXT NOT NULL,
+    PRIMARY KEY (feed_id, guid)
+);
+INSERT INTO feeds (url, last_synced) VALUES ('https://codeforces.com/blog/entry/rss', 1654000000);
+INSERT INTO feed_entries (feed_id, guid) VALUES (1, 'https://open.kattis.com/problems/autori');
+CREATE UNIQUE INDEX inbox_url ON inbox (url);
+ALTER TABLE inbox ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+UPDATE inbox SET position = id;
+ALTER TABLE items ADD COLUMN site TEXT;
+ALTER TABLE items ADD COLUMN problem_id TEXT;
+ALTER TABLE items ADD COLUMN contest TEXT;
+ALTER TABLE items ADD COLUMN problem_name TEXT;
+ALTER TABLE items ADD COLUMN title TEXT;
+ALTER TABLE items ADD COLUMN problem_difficulty TEXT;
+UPDATE items SET site = 'kattis', problem_id = 'faktor', problem_name = 'faktor', title = 'Faktor' WHERE id = 2;
+CREATE TABLE sample_tests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    input TEXT NOT NULL,
+    output TEXT NOT NULL
+);
+CREATE INDEX sample_tests_item_id ON sample_tests (item_id);
+ALTER TABLE reviews ADD COLUMN verdict TEXT;
+INSERT INTO sample_tests (item_id, input, output) VALUES (2, '10 10', '91');
+UPDATE reviews SET verdict = 'AC' WHERE item_id = 2;
+CREATE TABLE solutions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    review_id INTEGER REFERENCES reviews(id),
+    language TEXT NOT NULL,
+    source TEXT NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+CREATE INDEX solutions_item_id ON solutions (item_id);
+INSERT INTO solutions (item_id, review_id, language, source, timestamp) VALUES (2, 1, 'py', 'print(int(input()))', 1654000000);
+ALTER TABLE items ADD COLUMN scheduler_state TEXT;
+UPDATE items SET scheduler_state = '{"algorithm":"sm2","repetitions":1,"ease":2.6}' WHERE id = 2;
+ALTER TABLE items ADD COLUMN target_secs INTEGER;
+UPDATE items SET target_secs = 1800 WHERE id = 2;
+ALTER TABLE items ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+ALTER TABLE items ADD COLUMN buried_until INTEGER;
+UPDATE items SET status = 'suspended' WHERE id = 1;
+CREATE TABLE drips (
+    day INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL
+);
+CREATE TABLE drip_promotions (
+    day INTEGER NOT NULL REFERENCES drips(day),
+    item_id INTEGER REFERENCES items(id) ON DELETE SET NULL,
+    url TEXT NOT NULL
+);
+INSERT INTO drips (day, timestamp) VALUES (19146, 1654218000);
+INSERT INTO drip_promotions (day, item_id, url) VALUES (19146, 2, 'https://open.kattis.com/problems/faktor');
+PRAGMA user_version = 14;